@@ -39,6 +39,7 @@
 extends Reference
 
 var HexCell = preload("./HexCell.gd")
+var HexLayout = preload("res://lib/hex_layout.gdns")
 
 # Allow the user to scale the hex for fake perspective or somesuch
 #export(Vector2) var hex_scale = Vector2(1, 1) setget set_hex_scale
@@ -46,8 +47,8 @@ export(Vector2) var hex_scale = Vector2(32, 32) setget set_hex_scale
 
 var base_hex_size = Vector2(1, sqrt(3)/2)
 var hex_size
-var hex_transform
-var hex_transform_inv
+# every grid converts with its own scale, e.g. the minimap is smaller than the map
+var layout = HexLayout.new()
 
 func _init():
 	set_hex_scale(hex_scale)
@@ -57,12 +58,8 @@ func set_hex_scale(scale):
 	hex_scale = scale
 	# round so we actually get pixels
 	hex_size = (base_hex_size * hex_scale).round()
-	hex_transform = Transform2D(
-		Vector2(hex_size.x * 3/4, -hex_size.y / 2),
-		Vector2(0, -hex_size.y),
-		Vector2(0, 0)
-	)
-	hex_transform_inv = hex_transform.affine_inverse()
+	# the conversions themselves live in the native HexLayout
+	layout.configure(hex_size, Vector2(0, 0), false)
 	
 
 func get_zero_hex():
@@ -77,11 +74,11 @@ func from_cube_coords(coords: Vector3):
 func get_hex_center(hex):
 	# Returns hex's centre position on the projection plane
 	hex = HexCell.new(hex)
-	return hex_transform * hex.axial_coords
+	return layout.center(hex.cube_coords)
 	
 func get_hex_at(coords):
 	# Returns a HexCell at the given Vector2/3 on the projection plane
 	# If the given value is a Vector3, its x,z coords will be used
 	if typeof(coords) == TYPE_VECTOR3:
 		coords = Vector2(coords.x, coords.z)
-	return HexCell.new(hex_transform_inv * coords)
+	return HexCell.new(layout.coordinate_at(coords))
@@ -35,6 +35,12 @@ func update_hexes(rect: Rect2, force: bool = false):
 	if force:
 		$Terrain.clear()
 		$Yield.clear()
+	# generate the whole rect at once instead of chunk by chunk while filling it in
+	var from_corner = HexGrid.get_zero_hex()
+	from_corner.offset_coords = Vector2(floor(start_coords.x), floor(start_coords.y))
+	var to_corner = HexGrid.get_zero_hex()
+	to_corner.offset_coords = Vector2(ceil(stop_coords.x), ceil(stop_coords.y))
+	Terrain.prefetch(from_corner.cube_coords, to_corner.cube_coords)
 	# fill in new rect where necessary
 	for j in range(floor(start_coords.y), ceil(stop_coords.y)):
 		var i = floor(start_coords.x)
@@ -1,16 +1,61 @@
-use crate::observable::{Observable, Observers};
+use crate::observable::{Backpressure, Dispatch, Observable, Observers, DEFAULT_CAPACITY};
+use crossbeam::channel::{unbounded, Receiver, Sender};
+use scheduler::{Schedule, Scheduler, TimerHandle};
+use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use strum::{EnumCount, IntoEnumIterator};
+use strum_macros::{AsRefStr, EnumCount, EnumIter};
 
-#[derive(Copy, Clone, Eq, PartialOrd, PartialEq, Ord)]
-pub struct Tick(usize);
+pub mod calendar;
+pub mod clock_driver;
+pub mod scheduler;
+
+/// The phases of a single epoch, run in this order. A phase only starts once every observer of the
+/// previous phase has returned.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    EnumIter,
+    EnumCount,
+    AsRefStr,
+    Serialize,
+    Deserialize,
+)]
+pub enum Phase {
+    PreTick,
+    Consume,
+    Produce,
+    PostTick,
+}
+
+#[derive(Copy, Clone, Eq, PartialOrd, PartialEq, Ord, Serialize, Deserialize)]
+pub struct Tick {
+    epoch: usize,
+    phase: Phase,
+}
 
 impl Tick {
     pub fn epoch(&self) -> usize {
-        self.0
+        self.epoch
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    fn in_phase(&self, phase: Phase) -> Self {
+        Tick { phase, ..*self }
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialOrd, PartialEq, Ord)]
+#[derive(Copy, Clone, Eq, PartialOrd, PartialEq, Ord, Serialize, Deserialize)]
 pub struct Tock(usize);
 
 impl Tock {
@@ -21,22 +66,99 @@ impl Tock {
 
 impl From<Tick> for Tock {
     fn from(tick: Tick) -> Self {
-        Tock(tick.0)
+        Tock(tick.epoch)
     }
 }
 
-pub struct Clock {
-    epoch: AtomicUsize,
+struct Pipeline {
+    // phase observers are always notified inline, this is what makes a phase a barrier
+    phases: Vec<Observers<Tick>>,
+    scheduler: Arc<Scheduler>,
     tickers: Observers<Tick>,
     tockers: Observers<Tock>,
+    completed: Mutex<usize>,
+    completion: Condvar,
+}
+
+impl Pipeline {
+    fn run(&self, tick: Tick) {
+        self.tickers.queue_event(tick);
+        for phase in Phase::iter() {
+            let tick = tick.in_phase(phase);
+            // timers run first, so observers of the phase already see their effects
+            self.scheduler.run(&tick);
+            self.phases[phase as usize].queue_event(tick);
+        }
+        {
+            let mut completed = self.completed.lock().unwrap();
+            *completed = tick.epoch.max(*completed);
+            self.completion.notify_all();
+        }
+        self.tockers.queue_event(Tock::from(tick));
+    }
+
+    fn consume_ticks(self: &Arc<Self>, rx: Receiver<Tick>) {
+        let pipeline = Arc::clone(self);
+        thread::spawn(move || {
+            // runs until the clock and with it the sender is dropped
+            for tick in rx.iter() {
+                pipeline.run(tick);
+            }
+        });
+    }
+}
+
+pub struct Clock {
+    epoch: AtomicUsize,
+    pipeline: Arc<Pipeline>,
+    // only set for threaded dispatch, otherwise the phases run inline in `tick`
+    tx: Option<Sender<Tick>>,
 }
 
 impl Clock {
     pub fn new() -> Self {
+        Self::with_dispatch(Dispatch::default())
+    }
+
+    pub fn with_dispatch(dispatch: Dispatch) -> Self {
+        Self::with_backpressure(dispatch, Backpressure::default())
+    }
+
+    /// e.g. `Backpressure::Coalesce` so a slow ticker or tocker only sees the latest epoch, only
+    /// applies to threaded dispatch
+    pub fn with_backpressure(dispatch: Dispatch, backpressure: Backpressure) -> Self {
+        let pipeline = Arc::new(Pipeline {
+            phases: (0..Phase::COUNT)
+                .map(|_| Observers::synchronous())
+                .collect(),
+            scheduler: Arc::new(Scheduler::new()),
+            tickers: Self::notifications(dispatch, backpressure),
+            tockers: Self::notifications(dispatch, backpressure),
+            completed: Mutex::new(0),
+            completion: Condvar::new(),
+        });
+        let tx = match dispatch {
+            Dispatch::Threaded => {
+                let (tx, rx) = unbounded();
+                pipeline.consume_ticks(rx);
+                Some(tx)
+            }
+            Dispatch::Synchronous => None,
+        };
         Clock {
             epoch: AtomicUsize::new(0),
-            tickers: Observers::new(),
-            tockers: Observers::new(),
+            pipeline,
+            tx,
+        }
+    }
+
+    fn notifications<E: 'static + Send + Sync>(
+        dispatch: Dispatch,
+        backpressure: Backpressure,
+    ) -> Observers<E> {
+        match dispatch {
+            Dispatch::Threaded => Observers::with_backpressure(DEFAULT_CAPACITY, backpressure),
+            Dispatch::Synchronous => Observers::synchronous(),
         }
     }
 
@@ -44,35 +166,115 @@ impl Clock {
         self.epoch.load(Ordering::Acquire)
     }
 
+    /// the last epoch that went through all phases
+    pub fn completed_epoch(&self) -> usize {
+        *self.pipeline.completed.lock().unwrap()
+    }
+
+    /// block until every phase of `epoch` has completed
+    pub fn wait_for(&self, epoch: usize) {
+        let mut completed = self.pipeline.completed.lock().unwrap();
+        while *completed < epoch {
+            completed = self.pipeline.completion.wait(completed).unwrap();
+        }
+    }
+
+    /// notified when an epoch starts, before any of its phases
     pub fn tickers(&self) -> &Observers<Tick> {
-        &self.tickers
+        &self.pipeline.tickers
     }
 
+    /// notified when an epoch has completed all of its phases
     pub fn tockers(&self) -> &Observers<Tock> {
-        &self.tockers
+        &self.pipeline.tockers
     }
 
-    pub fn tick(&self) {
-        self.epoch.fetch_add(1, Ordering::Release);
-        let tick = Tick(self.epoch());
-        self.notify_all(tick);
-        self.tock(tick);
+    pub fn phase(&self, phase: Phase) -> &Observers<Tick> {
+        &self.pipeline.phases[phase as usize]
+    }
+
+    pub fn scheduler(&self) -> &Arc<Scheduler> {
+        &self.pipeline.scheduler
+    }
+
+    /// run `job` once in `phase`, `ticks` epochs from now
+    pub fn after(&self, ticks: usize, phase: Phase, job: &str) -> TimerHandle {
+        self.scheduler().at(self.epoch() + ticks, phase, job)
+    }
+
+    /// run `job` in `phase` every `ticks` epochs, starting `ticks` epochs from now
+    pub fn every(&self, ticks: usize, phase: Phase, job: &str) -> TimerHandle {
+        self.scheduler()
+            .recurring(self.epoch() + ticks, ticks, phase, job)
+    }
+
+    /// the pending timers, to be saved along with the game
+    pub fn schedule(&self) -> Schedule {
+        self.scheduler().schedule(self.epoch())
     }
 
-    fn tock(&self, tick: Tick) {
-        let tock = Tock::from(tick);
-        self.notify_all(tock);
+    pub fn restore_schedule(&self, schedule: Schedule) {
+        self.scheduler().restore(schedule, self.epoch())
+    }
+
+    pub fn tick(&self) {
+        let epoch = self.epoch.fetch_add(1, Ordering::AcqRel) + 1;
+        let tick = Tick {
+            epoch,
+            phase: Phase::PreTick,
+        };
+        match &self.tx {
+            Some(tx) => tx.send(tick).unwrap(),
+            None => self.pipeline.run(tick),
+        }
     }
 }
 
 impl Observable<Tick> for Clock {
     fn observers(&self) -> &Observers<Tick> {
-        &self.tickers
+        self.tickers()
     }
 }
 
 impl Observable<Tock> for Clock {
     fn observers(&self) -> &Observers<Tock> {
-        &self.tockers
+        self.tockers()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observable::{Observer, Subscription};
+
+    struct PhaseRecorder {
+        log: Mutex<Vec<(usize, Phase)>>,
+    }
+
+    impl Observer<Tick> for PhaseRecorder {
+        fn notify(&self, tick: &Tick) {
+            self.log.lock().unwrap().push((tick.epoch(), tick.phase()));
+        }
+    }
+
+    #[test]
+    fn test_phase_order() {
+        let clock = Clock::new();
+        let recorder = Arc::new(PhaseRecorder {
+            log: Mutex::new(vec![]),
+        });
+        // registered in reverse to make sure the phase, not the registration, decides the order
+        let _subscriptions: Vec<Subscription> = Phase::iter()
+            .rev()
+            .map(|phase| clock.phase(phase).register(&recorder))
+            .collect();
+        clock.tick();
+        clock.tick();
+        clock.wait_for(2);
+        assert_eq!(clock.completed_epoch(), 2);
+        let expected: Vec<(usize, Phase)> = (1..=2)
+            .flat_map(|epoch| Phase::iter().map(move |phase| (epoch, phase)))
+            .collect();
+        assert_eq!(*recorder.log.lock().unwrap(), expected);
     }
 }
@@ -0,0 +1,272 @@
+use crate::clock::{Clock, Phase, Tick};
+use crate::observable::{Dispatch, Observable, Observer, Observers, Subscribed};
+use serde::{Deserialize, Serialize};
+use strum::{EnumCount, IntoEnumIterator};
+use strum_macros::{AsRefStr, EnumCount, EnumIter, EnumString, EnumVariantNames};
+
+pub const DAYS_PER_MONTH: usize = 30;
+pub const DAYS_PER_YEAR: usize = DAYS_PER_MONTH * Month::COUNT;
+pub const DEFAULT_START_YEAR: usize = 1400;
+
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumIter,
+    EnumCount,
+    AsRefStr,
+    EnumString,
+    EnumVariantNames,
+    Serialize,
+    Deserialize,
+)]
+pub enum Month {
+    January,
+    February,
+    March,
+    April,
+    May,
+    June,
+    July,
+    August,
+    September,
+    October,
+    November,
+    December,
+}
+
+impl Month {
+    fn from_index(index: usize) -> Self {
+        Month::iter().nth(index % Month::COUNT).unwrap()
+    }
+
+    /// meteorological season on the northern hemisphere
+    pub fn season(&self) -> Season {
+        match self {
+            Month::March | Month::April | Month::May => Season::Spring,
+            Month::June | Month::July | Month::August => Season::Summer,
+            Month::September | Month::October | Month::November => Season::Autumn,
+            Month::December | Month::January | Month::February => Season::Winter,
+        }
+    }
+
+    /// the season at the given latitude in degrees, the southern hemisphere is half a year off
+    pub fn season_at(&self, latitude: f64) -> Season {
+        let season = self.season();
+        if latitude < 0. {
+            season.opposite()
+        } else {
+            season
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumIter,
+    EnumCount,
+    AsRefStr,
+    EnumString,
+    EnumVariantNames,
+    Serialize,
+    Deserialize,
+)]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    pub fn opposite(&self) -> Self {
+        match self {
+            Season::Spring => Season::Autumn,
+            Season::Summer => Season::Winter,
+            Season::Autumn => Season::Spring,
+            Season::Winter => Season::Summer,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Date {
+    year: usize,
+    // zero based, so dates order naturally
+    month: usize,
+    day: usize,
+}
+
+impl Date {
+    pub fn year(&self) -> usize {
+        self.year
+    }
+
+    pub fn month(&self) -> Month {
+        Month::from_index(self.month)
+    }
+
+    /// day of the month, starting at 1
+    pub fn day(&self) -> usize {
+        self.day + 1
+    }
+
+    /// northern hemisphere season, see `Month::season_at` for a specific latitude
+    pub fn season(&self) -> Season {
+        self.month().season()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayChanged {
+    pub epoch: usize,
+    pub date: Date,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeasonChanged {
+    pub epoch: usize,
+    pub date: Date,
+    pub season: Season,
+}
+
+/// Maps clock epochs to dates and announces new days and seasons.
+pub struct Calendar {
+    ticks_per_day: usize,
+    start_year: usize,
+    days: Observers<DayChanged>,
+    seasons: Observers<SeasonChanged>,
+}
+
+impl Calendar {
+    pub fn new(ticks_per_day: usize, start_year: usize, dispatch: Dispatch) -> Self {
+        Calendar {
+            ticks_per_day: ticks_per_day.max(1),
+            start_year,
+            days: Observers::with_dispatch(dispatch),
+            seasons: Observers::with_dispatch(dispatch),
+        }
+    }
+
+    /// a calendar that follows the clock, announcing changes at the start of a tick
+    pub fn subscribed(self, clock: &Clock) -> Subscribed<Self> {
+        Subscribed::new(self).subscribe(clock.phase(Phase::PreTick))
+    }
+
+    pub fn ticks_per_day(&self) -> usize {
+        self.ticks_per_day
+    }
+
+    pub fn start_year(&self) -> usize {
+        self.start_year
+    }
+
+    pub fn date(&self, epoch: usize) -> Date {
+        let days = epoch / self.ticks_per_day;
+        Date {
+            year: self.start_year + days / DAYS_PER_YEAR,
+            month: (days % DAYS_PER_YEAR) / DAYS_PER_MONTH,
+            day: days % DAYS_PER_MONTH,
+        }
+    }
+
+    pub fn days(&self) -> &Observers<DayChanged> {
+        &self.days
+    }
+
+    pub fn seasons(&self) -> &Observers<SeasonChanged> {
+        &self.seasons
+    }
+}
+
+impl Observer<Tick> for Calendar {
+    fn notify(&self, tick: &Tick) {
+        let epoch = tick.epoch();
+        let date = self.date(epoch);
+        let previous = self.date(epoch.saturating_sub(1));
+        if date == previous {
+            return;
+        }
+        self.notify_all(DayChanged { epoch, date });
+        if date.season() != previous.season() {
+            self.notify_all(SeasonChanged {
+                epoch,
+                date,
+                season: date.season(),
+            });
+        }
+    }
+}
+
+impl Observable<DayChanged> for Calendar {
+    fn observers(&self) -> &Observers<DayChanged> {
+        &self.days
+    }
+}
+
+impl Observable<SeasonChanged> for Calendar {
+    fn observers(&self) -> &Observers<SeasonChanged> {
+        &self.seasons
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_date() {
+        let calendar = Calendar::new(4, DEFAULT_START_YEAR, Dispatch::Synchronous);
+        let date = calendar.date(0);
+        assert_eq!(
+            (date.year(), date.month(), date.day()),
+            (1400, Month::January, 1)
+        );
+        let date = calendar.date(4 * (DAYS_PER_YEAR + 2 * DAYS_PER_MONTH + 3) + 3);
+        assert_eq!(
+            (date.year(), date.month(), date.day()),
+            (1401, Month::March, 4)
+        );
+        assert_eq!(date.season(), Season::Spring);
+        assert_eq!(date.month().season_at(-45.), Season::Autumn);
+    }
+
+    #[derive(Default)]
+    struct SeasonRecorder(Mutex<Vec<Season>>);
+
+    impl Observer<SeasonChanged> for SeasonRecorder {
+        fn notify(&self, event: &SeasonChanged) {
+            self.0.lock().unwrap().push(event.season);
+        }
+    }
+
+    #[test]
+    fn test_season_changes() {
+        let clock = Clock::with_dispatch(Dispatch::Synchronous);
+        let calendar =
+            Calendar::new(1, DEFAULT_START_YEAR, Dispatch::Synchronous).subscribed(&clock);
+        let recorder = Arc::new(SeasonRecorder::default());
+        let _subscription = calendar.seasons().register(&recorder);
+        for _ in 0..DAYS_PER_YEAR {
+            clock.tick();
+        }
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![
+                Season::Spring,
+                Season::Summer,
+                Season::Autumn,
+                Season::Winter
+            ]
+        );
+    }
+}
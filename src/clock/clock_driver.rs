@@ -0,0 +1,251 @@
+use crate::clock::Clock;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+pub const MIN_SPEED: f64 = 0.5;
+pub const MAX_SPEED: f64 = 8.;
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+/// shorter intervals would keep the driver thread spinning
+pub const MIN_INTERVAL: Duration = Duration::from_millis(1);
+/// longer intervals would leave the clock standing still for all practical purposes
+pub const MAX_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// how many ticks the driver may fire back to back to catch up after a stall
+const MAX_CATCH_UP: u32 = 4;
+
+struct DriverState {
+    paused: bool,
+    speed: f64,
+    interval: Duration,
+    pending_steps: usize,
+    shutdown: bool,
+    next_tick: Instant,
+}
+
+impl DriverState {
+    fn step_duration(&self) -> Duration {
+        self.interval.div_f64(self.speed)
+    }
+}
+
+struct DriverControl {
+    state: Mutex<DriverState>,
+    changed: Condvar,
+}
+
+/// Drives a `Clock` from a timer thread with fixed-step ticking.
+pub struct ClockDriver {
+    control: Arc<DriverControl>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ClockDriver {
+    /// the driver starts out paused, use `resume` or `step` to advance the clock
+    pub fn new(clock: &Arc<Clock>) -> Self {
+        Self::with_interval(clock, DEFAULT_INTERVAL)
+    }
+
+    /// the interval is clamped to `MIN_INTERVAL..=MAX_INTERVAL`
+    pub fn with_interval(clock: &Arc<Clock>, interval: Duration) -> Self {
+        let interval = interval.clamp(MIN_INTERVAL, MAX_INTERVAL);
+        let control = Arc::new(DriverControl {
+            state: Mutex::new(DriverState {
+                paused: true,
+                speed: 1.,
+                interval,
+                pending_steps: 0,
+                shutdown: false,
+                next_tick: Instant::now(),
+            }),
+            changed: Condvar::new(),
+        });
+        let handle = Self::drive(Arc::downgrade(clock), control.clone());
+        ClockDriver {
+            control,
+            handle: Some(handle),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, DriverState> {
+        self.control.state.lock().unwrap()
+    }
+
+    fn update(&self, update: impl FnOnce(&mut DriverState)) {
+        update(&mut self.state());
+        self.control.changed.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state().paused
+    }
+
+    pub fn pause(&self) {
+        self.update(|state| state.paused = true);
+    }
+
+    pub fn resume(&self) {
+        self.update(|state| {
+            if state.paused {
+                state.paused = false;
+                state.next_tick = Instant::now() + state.step_duration();
+            }
+        });
+    }
+
+    /// advance the clock by exactly one tick, only has an effect while paused
+    pub fn step(&self) {
+        self.update(|state| {
+            if state.paused {
+                state.pending_steps += 1;
+            }
+        });
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.state().speed
+    }
+
+    /// speed multiplier, clamped to `MIN_SPEED..=MAX_SPEED`, a speed that isn't a number is
+    /// ignored
+    pub fn set_speed(&self, speed: f64) {
+        if speed.is_nan() {
+            return;
+        }
+        self.update(|state| {
+            state.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+            state.next_tick = Instant::now() + state.step_duration();
+        });
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.state().interval
+    }
+
+    /// real time between two ticks at a speed of 1x, clamped to `MIN_INTERVAL..=MAX_INTERVAL`
+    pub fn set_interval(&self, interval: Duration) {
+        self.update(|state| {
+            state.interval = interval.clamp(MIN_INTERVAL, MAX_INTERVAL);
+            state.next_tick = Instant::now() + state.step_duration();
+        });
+    }
+
+    fn drive(clock: Weak<Clock>, control: Arc<DriverControl>) -> JoinHandle<()> {
+        thread::spawn(move || loop {
+            let mut state = control.state.lock().unwrap();
+            if state.shutdown {
+                return;
+            }
+            let ticks = if state.paused {
+                if state.pending_steps == 0 {
+                    drop(control.changed.wait(state).unwrap());
+                    continue;
+                }
+                state.pending_steps -= 1;
+                1
+            } else {
+                let now = Instant::now();
+                if now < state.next_tick {
+                    let timeout = state.next_tick - now;
+                    drop(control.changed.wait_timeout(state, timeout).unwrap());
+                    continue;
+                }
+                // fixed step: fire every tick that has become due, but don't spiral after a stall
+                let step_duration = state.step_duration();
+                let mut ticks = 0;
+                while state.next_tick <= now && ticks < MAX_CATCH_UP {
+                    state.next_tick += step_duration;
+                    ticks += 1;
+                }
+                if state.next_tick <= now {
+                    state.next_tick = now + step_duration;
+                }
+                ticks
+            };
+            drop(state);
+            match clock.upgrade() {
+                Some(clock) => {
+                    (0..ticks).for_each(|_| clock.tick());
+                    // don't queue up epochs faster than the simulation can complete them
+                    clock.wait_for(clock.epoch());
+                }
+                // the clock is gone, nothing left to drive
+                None => return,
+            }
+        })
+    }
+}
+
+impl Drop for ClockDriver {
+    fn drop(&mut self) {
+        self.update(|state| state.shutdown = true);
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait_for_epoch(clock: &Clock, epoch: usize) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while clock.epoch() < epoch {
+            assert!(Instant::now() < deadline, "clock did not advance in time");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_step_while_paused() {
+        let clock = Arc::new(Clock::new());
+        let driver = ClockDriver::new(&clock);
+        assert!(driver.is_paused());
+        driver.step();
+        driver.step();
+        wait_for_epoch(&clock, 2);
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(clock.epoch(), 2);
+    }
+
+    #[test]
+    fn test_resume_and_pause() {
+        let clock = Arc::new(Clock::new());
+        let driver = ClockDriver::with_interval(&clock, Duration::from_millis(2));
+        driver.set_speed(100.);
+        assert_eq!(driver.speed(), MAX_SPEED);
+        driver.resume();
+        wait_for_epoch(&clock, 3);
+        driver.pause();
+        // a tick that was already due may still be in flight
+        thread::sleep(Duration::from_millis(10));
+        let epoch = clock.epoch();
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(clock.epoch(), epoch);
+    }
+
+    #[test]
+    fn test_min_interval() {
+        let clock = Arc::new(Clock::new());
+        let driver = ClockDriver::with_interval(&clock, Duration::from_secs(0));
+        assert_eq!(driver.interval(), MIN_INTERVAL);
+        driver.set_interval(Duration::from_secs(2));
+        assert_eq!(driver.interval(), Duration::from_secs(2));
+        driver.set_interval(Duration::from_nanos(1));
+        assert_eq!(driver.interval(), MIN_INTERVAL);
+        driver.set_interval(Duration::from_secs(u64::MAX));
+        assert_eq!(driver.interval(), MAX_INTERVAL);
+    }
+
+    #[test]
+    fn test_speed_not_a_number() {
+        let clock = Arc::new(Clock::new());
+        let driver = ClockDriver::new(&clock);
+        driver.set_speed(2.);
+        driver.set_speed(f64::NAN);
+        assert_eq!(driver.speed(), 2.);
+        driver.set_speed(f64::INFINITY);
+        assert_eq!(driver.speed(), MAX_SPEED);
+    }
+}
@@ -0,0 +1,343 @@
+use crate::clock::{Phase, Tick};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use strum::EnumCount;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct TimerId(usize);
+
+/// A pending job, identified by the name it was registered under so it can be saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timer {
+    id: TimerId,
+    job: String,
+    phase: Phase,
+    due: usize,
+    every: Option<usize>,
+}
+
+impl Timer {
+    pub fn id(&self) -> TimerId {
+        self.id
+    }
+
+    pub fn job(&self) -> &str {
+        &self.job
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// the epoch the timer fires next
+    pub fn due(&self) -> usize {
+        self.due
+    }
+
+    /// the interval of a recurring timer
+    pub fn every(&self) -> Option<usize> {
+        self.every
+    }
+}
+
+/// The saveable state of a `Scheduler`, due epochs are relative to the epoch it was taken at.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Schedule {
+    timers: Vec<ScheduledTimer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ScheduledTimer {
+    id: TimerId,
+    job: String,
+    phase: Phase,
+    remaining: usize,
+    every: Option<usize>,
+}
+
+pub type Job = dyn Fn(&Timer, &Tick) + Send + Sync;
+
+/// ordered by due epoch, then by scheduling order
+type TimersStore = BTreeMap<(usize, TimerId), Timer>;
+
+/// Runs named jobs at a given epoch and phase, either once or recurring.
+pub struct Scheduler {
+    jobs: RwLock<HashMap<String, Arc<Job>>>,
+    // one store per phase, like the clock's phase observers
+    timers: Mutex<Vec<TimersStore>>,
+    next_id: AtomicUsize,
+}
+
+impl Scheduler {
+    pub(super) fn new() -> Self {
+        Scheduler {
+            jobs: Default::default(),
+            timers: Mutex::new((0..Phase::COUNT).map(|_| TimersStore::new()).collect()),
+            next_id: AtomicUsize::new(0),
+        }
+    }
+
+    /// register the job timers refer to by `name`, replaces a job of the same name
+    pub fn register_job(
+        &self,
+        name: impl Into<String>,
+        job: impl Fn(&Timer, &Tick) + Send + Sync + 'static,
+    ) {
+        self.jobs
+            .write()
+            .unwrap()
+            .insert(name.into(), Arc::new(job));
+    }
+
+    pub fn deregister_job(&self, name: &str) -> bool {
+        self.jobs.write().unwrap().remove(name).is_some()
+    }
+
+    /// run `job` once in `phase` of `epoch`, an epoch that has already passed runs with the next tick
+    pub fn at(self: &Arc<Self>, epoch: usize, phase: Phase, job: &str) -> TimerHandle {
+        self.insert(job, phase, epoch, None)
+    }
+
+    /// run `job` in `phase` of `epoch` and then every `every` epochs after that
+    pub fn recurring(
+        self: &Arc<Self>,
+        epoch: usize,
+        every: usize,
+        phase: Phase,
+        job: &str,
+    ) -> TimerHandle {
+        self.insert(job, phase, epoch, Some(every.max(1)))
+    }
+
+    fn insert(
+        self: &Arc<Self>,
+        job: &str,
+        phase: Phase,
+        due: usize,
+        every: Option<usize>,
+    ) -> TimerHandle {
+        let id = TimerId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.timers.lock().unwrap()[phase as usize].insert(
+            (due, id),
+            Timer {
+                id,
+                job: job.to_string(),
+                phase,
+                due,
+                every,
+            },
+        );
+        TimerHandle {
+            id,
+            scheduler: Arc::downgrade(self),
+        }
+    }
+
+    /// returns false if the timer already fired or was cancelled
+    pub fn cancel(&self, id: TimerId) -> bool {
+        let mut timers = self.timers.lock().unwrap();
+        for store in timers.iter_mut() {
+            if let Some(key) = store.keys().find(|(_, other)| other == &id).copied() {
+                store.remove(&key);
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn get(&self, id: TimerId) -> Option<Timer> {
+        self.timers
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|store| store.values())
+            .find(|timer| timer.id == id)
+            .cloned()
+    }
+
+    /// all pending timers in phase order, then by due epoch
+    pub fn pending(&self) -> Vec<Timer> {
+        self.timers
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|store| store.values().cloned())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.timers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|store| store.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// the pending timers as seen from `epoch`
+    pub fn schedule(&self, epoch: usize) -> Schedule {
+        let timers = self
+            .pending()
+            .into_iter()
+            .map(|timer| ScheduledTimer {
+                id: timer.id,
+                job: timer.job,
+                phase: timer.phase,
+                remaining: timer.due.saturating_sub(epoch),
+                every: timer.every,
+            })
+            .collect();
+        Schedule { timers }
+    }
+
+    /// replace the pending timers with a saved `schedule`, resuming at `epoch`
+    pub fn restore(&self, schedule: Schedule, epoch: usize) {
+        let mut timers = self.timers.lock().unwrap();
+        timers.iter_mut().for_each(|store| store.clear());
+        for saved in schedule.timers {
+            // keep new ids clear of the restored ones
+            self.next_id.fetch_max(saved.id.0 + 1, Ordering::Relaxed);
+            let due = epoch + saved.remaining;
+            timers[saved.phase as usize].insert(
+                (due, saved.id),
+                Timer {
+                    id: saved.id,
+                    job: saved.job,
+                    phase: saved.phase,
+                    due,
+                    every: saved.every,
+                },
+            );
+        }
+    }
+
+    /// fire every timer of the tick's phase that is due by its epoch
+    pub(super) fn run(&self, tick: &Tick) {
+        let due: Vec<Timer> = {
+            let mut timers = self.timers.lock().unwrap();
+            let store = &mut timers[tick.phase() as usize];
+            let later = store.split_off(&(tick.epoch() + 1, TimerId(0)));
+            let due = std::mem::replace(store, later);
+            // recurring timers are rescheduled before their jobs run, so a job can cancel itself
+            for timer in due.values() {
+                if let Some(every) = timer.every {
+                    let next = Timer {
+                        due: tick.epoch() + every,
+                        ..timer.clone()
+                    };
+                    store.insert((next.due, next.id), next);
+                }
+            }
+            due.into_values().collect()
+        };
+        for timer in due {
+            let job = self.jobs.read().unwrap().get(&timer.job).cloned();
+            match job {
+                Some(job) => job(&timer, tick),
+                None => warn!("no job {} registered for timer {:?}", timer.job, timer.id),
+            }
+        }
+    }
+}
+
+/// Handle to a pending timer. Unlike a `Subscription` dropping it does not cancel the timer.
+pub struct TimerHandle {
+    id: TimerId,
+    scheduler: Weak<Scheduler>,
+}
+
+impl TimerHandle {
+    pub fn id(&self) -> TimerId {
+        self.id
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.scheduler
+            .upgrade()
+            .map_or(false, |scheduler| scheduler.get(self.id).is_some())
+    }
+
+    /// returns false if the timer already fired or was cancelled
+    pub fn cancel(self) -> bool {
+        self.scheduler
+            .upgrade()
+            .map_or(false, |scheduler| scheduler.cancel(self.id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::{Clock, Phase};
+    use crate::observable::Dispatch;
+    use std::sync::{Arc, Mutex};
+
+    type Log = Arc<Mutex<Vec<(String, usize, Phase)>>>;
+
+    fn recording_clock() -> (Clock, Log) {
+        let clock = Clock::with_dispatch(Dispatch::Synchronous);
+        let log = Arc::new(Mutex::new(vec![]));
+        for name in &["once", "recurring"] {
+            let log = log.clone();
+            clock.scheduler().register_job(*name, move |timer, tick| {
+                log.lock()
+                    .unwrap()
+                    .push((timer.job().to_string(), tick.epoch(), tick.phase()))
+            });
+        }
+        (clock, log)
+    }
+
+    #[test]
+    fn test_one_shot_and_recurring() {
+        let (clock, log) = recording_clock();
+        clock.after(2, Phase::PostTick, "once");
+        let recurring = clock.every(2, Phase::PreTick, "recurring");
+        let cancelled = clock.after(1, Phase::Produce, "once");
+        assert!(cancelled.cancel());
+        for _ in 0..4 {
+            clock.tick();
+        }
+        assert!(recurring.is_pending());
+        assert!(recurring.cancel());
+        clock.tick();
+        clock.tick();
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                ("recurring".to_string(), 2, Phase::PreTick),
+                ("once".to_string(), 2, Phase::PostTick),
+                ("recurring".to_string(), 4, Phase::PreTick),
+            ]
+        );
+        assert!(clock.scheduler().is_empty());
+    }
+
+    #[test]
+    fn test_restore_schedule() {
+        let (clock, _) = recording_clock();
+        clock.tick();
+        clock.after(3, Phase::Consume, "once");
+        clock.every(5, Phase::Produce, "recurring");
+        let saved = serde_json::to_string(&clock.schedule()).unwrap();
+
+        let (restored, log) = recording_clock();
+        restored.restore_schedule(serde_json::from_str(&saved).unwrap());
+        for _ in 0..5 {
+            restored.tick();
+        }
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                ("once".to_string(), 3, Phase::Consume),
+                ("recurring".to_string(), 5, Phase::Produce),
+            ]
+        );
+    }
+}
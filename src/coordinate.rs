@@ -1,7 +1,13 @@
-mod dist;
-mod faces;
+pub mod dist;
+pub mod faces;
 pub mod indexed;
+pub mod layout;
+pub mod line;
 pub mod range;
+pub mod symmetry;
+pub mod wrap;
+
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Offset {
@@ -22,7 +28,9 @@ impl Offset {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default, Serialize, Deserialize,
+)]
 pub struct Coordinate {
     x: i32,
     y: i32,
@@ -37,15 +45,18 @@ impl Coordinate {
 
     /// convert floating point coordinates to the nearest coordinate
     pub fn round(x: f64, y: f64) -> Self {
+        let z = -x - y;
         let mut rx = x.round();
         let mut ry = y.round();
-        let rz = -rx - ry;
+        let rz = z.round();
 
+        // reset the component with the largest rounding error, so x + y + z stays 0
         let x_diff = (rx - x).abs();
         let y_diff = (ry - y).abs();
-        if x_diff > y_diff {
+        let z_diff = (rz - z).abs();
+        if x_diff > y_diff && x_diff > z_diff {
             rx = -ry - rz;
-        } else {
+        } else if y_diff > z_diff {
             ry = -rx - rz;
         }
         Self::new(rx as i32, ry as i32)
@@ -87,7 +98,7 @@ impl From<&Coordinate> for Offset {
 
 impl From<Coordinate> for Offset {
     fn from(coordinate: Coordinate) -> Self {
-        coordinate.into()
+        (&coordinate).into()
     }
 }
 
@@ -1,3 +1,4 @@
+use crate::coordinate::wrap::Wrap;
 use crate::coordinate::Coordinate;
 
 pub trait Dist {
@@ -10,10 +11,22 @@ pub trait Dist {
     }
 
     fn dist(&self, other: &Coordinate) -> u32;
+
+    /// the shorter way, east or west around the world
+    fn dist_wrapped(&self, other: &Coordinate, wrap: &Wrap) -> u32;
 }
 
 impl Dist for Coordinate {
     fn dist(&self, other: &Coordinate) -> u32 {
         Self::dist_between(self, other)
     }
+
+    fn dist_wrapped(&self, other: &Coordinate, wrap: &Wrap) -> u32 {
+        let other = wrap.normalize(other);
+        wrap.neighboring_laps(&wrap.normalize(self))
+            .iter()
+            .map(|lap| Self::dist_between(lap, &other))
+            .min()
+            .unwrap()
+    }
 }
@@ -1,6 +1,8 @@
 use crate::coordinate::dist::Dist;
+use crate::coordinate::range::{Range, RangeFactory};
 use crate::coordinate::Coordinate;
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Face {
     Left,
     TopLeft,
@@ -17,7 +19,7 @@ pub trait Faces {
             return Face::None;
         }
         let dist = c1 - c2;
-        return if dist.x == 0 {
+        if dist.x == 0 {
             if dist.y < 0 {
                 Face::BottomLeft
             } else {
@@ -33,14 +35,23 @@ pub trait Faces {
             Face::TopLeft
         } else {
             Face::Left
-        };
+        }
     }
 
     fn touching_face(&self, other: &Coordinate) -> Face;
+
+    /// the neighbor on the other side of `face`, `None` for `Face::None`
+    fn neighbor_at(&self, face: &Face) -> Option<Coordinate>;
 }
 
 impl Faces for Coordinate {
     fn touching_face(&self, other: &Coordinate) -> Face {
         Self::touching_face_between(self, other)
     }
+
+    fn neighbor_at(&self, face: &Face) -> Option<Coordinate> {
+        Range::neighbors(self)
+            .iter()
+            .find(|neighbor| &self.touching_face(neighbor) == face)
+    }
 }
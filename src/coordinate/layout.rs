@@ -0,0 +1,155 @@
+use crate::coordinate::Coordinate;
+use std::f64::consts::PI;
+
+const SQRT_3: f64 = 1.732_050_807_568_877_2;
+
+/// Which way the hexes point, the godot scenes use flat hexes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Orientation {
+    Pointy,
+    Flat,
+}
+
+impl Default for Orientation {
+    fn default() -> Self {
+        Orientation::Flat
+    }
+}
+
+impl Orientation {
+    // https://www.redblobgames.com/grids/hexagons/implementation.html#layout
+    // the matrices take x and z, as z grows to the south on screen
+    fn forward(&self) -> [f64; 4] {
+        match self {
+            Orientation::Pointy => [SQRT_3, SQRT_3 / 2., 0., 3. / 2.],
+            Orientation::Flat => [3. / 2., 0., SQRT_3 / 2., SQRT_3],
+        }
+    }
+
+    fn backward(&self) -> [f64; 4] {
+        match self {
+            Orientation::Pointy => [SQRT_3 / 3., -1. / 3., 0., 2. / 3.],
+            Orientation::Flat => [2. / 3., 0., -1. / 3., SQRT_3 / 3.],
+        }
+    }
+
+    /// in sixths of a full turn
+    fn start_angle(&self) -> f64 {
+        match self {
+            Orientation::Pointy => 0.5,
+            Orientation::Flat => 0.,
+        }
+    }
+}
+
+/// Converts between coordinates and pixel or world positions, with y growing downwards.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Layout {
+    orientation: Orientation,
+    size: (f64, f64),
+    origin: (f64, f64),
+}
+
+impl Layout {
+    /// `size` is the distance from the center to a corner, it can differ along x and y
+    pub fn new(orientation: Orientation, size: (f64, f64), origin: (f64, f64)) -> Self {
+        Layout {
+            orientation,
+            size,
+            origin,
+        }
+    }
+
+    /// from the width and height of a whole hex, as the godot scenes measure them
+    pub fn with_hex_size(
+        orientation: Orientation,
+        hex_size: (f64, f64),
+        origin: (f64, f64),
+    ) -> Self {
+        let (width, height) = hex_size;
+        let size = match orientation {
+            Orientation::Pointy => (width / SQRT_3, height / 2.),
+            Orientation::Flat => (width / 2., height / SQRT_3),
+        };
+        Layout::new(orientation, size, origin)
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    pub fn size(&self) -> (f64, f64) {
+        self.size
+    }
+
+    pub fn origin(&self) -> (f64, f64) {
+        self.origin
+    }
+
+    /// the center of `coordinate`
+    pub fn center(&self, coordinate: &Coordinate) -> (f64, f64) {
+        let [f0, f1, f2, f3] = self.orientation.forward();
+        let (x, z) = (coordinate.x() as f64, coordinate.z() as f64);
+        (
+            (f0 * x + f1 * z) * self.size.0 + self.origin.0,
+            (f2 * x + f3 * z) * self.size.1 + self.origin.1,
+        )
+    }
+
+    /// the coordinate whose hex contains `pixel`
+    pub fn coordinate_at(&self, pixel: (f64, f64)) -> Coordinate {
+        let [b0, b1, b2, b3] = self.orientation.backward();
+        let px = (pixel.0 - self.origin.0) / self.size.0;
+        let py = (pixel.1 - self.origin.1) / self.size.1;
+        let x = b0 * px + b1 * py;
+        let z = b2 * px + b3 * py;
+        Coordinate::round(x, -x - z)
+    }
+
+    /// the corners of the hex at `coordinate`, clockwise on screen
+    pub fn corners(&self, coordinate: &Coordinate) -> [(f64, f64); 6] {
+        let (center_x, center_y) = self.center(coordinate);
+        let mut corners = [(0., 0.); 6];
+        for (idx, corner) in corners.iter_mut().enumerate() {
+            let angle = 2. * PI * (self.orientation.start_angle() + idx as f64) / 6.;
+            *corner = (
+                center_x + self.size.0 * angle.cos(),
+                center_y + self.size.1 * angle.sin(),
+            );
+        }
+        corners
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinate::range::{Range, RangeFactory};
+
+    #[test]
+    fn test_layout() {
+        for orientation in [Orientation::Pointy, Orientation::Flat].iter() {
+            let layout = Layout::with_hex_size(*orientation, (32., 28.), (100., -50.));
+            for coordinate in Range::circle0(3).iter() {
+                let (x, y) = layout.center(&coordinate);
+                assert_eq!(layout.coordinate_at((x, y)), coordinate);
+                // still inside, just off the center
+                assert_eq!(layout.coordinate_at((x + 5., y - 5.)), coordinate);
+                for (corner_x, corner_y) in layout.corners(&coordinate).iter() {
+                    // a bit towards the center from every corner is still the same hex
+                    let inside = (
+                        corner_x + (x - corner_x) * 0.1,
+                        corner_y + (y - corner_y) * 0.1,
+                    );
+                    assert_eq!(layout.coordinate_at(inside), coordinate);
+                }
+            }
+        }
+        // the godot hex grid: +x is north east and +y is north, with y pointing down on screen
+        let layout = Layout::with_hex_size(Orientation::Flat, (32., 28.), (0., 0.));
+        let (x, y) = layout.center(&Coordinate::new(1, 0));
+        assert!((x - 24.).abs() < 1e-9 && (y + 14.).abs() < 1e-9);
+        let (x, y) = layout.center(&Coordinate::new(0, 1));
+        assert!(x.abs() < 1e-9 && (y + 28.).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,87 @@
+use crate::coordinate::dist::Dist;
+use crate::coordinate::Coordinate;
+
+// nudge the endpoints off the hex edges, so lines along an edge consistently pick the same side
+const EPSILON: f64 = 1e-6;
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+/// all coordinates on the line from `start` to `end`, in order and including both
+pub fn line(start: &Coordinate, end: &Coordinate) -> Vec<Coordinate> {
+    let steps = start.dist(end);
+    if steps == 0 {
+        return vec![*start];
+    }
+    let (ax, ay) = (start.x() as f64 + EPSILON, start.y() as f64 + EPSILON);
+    let (bx, by) = (end.x() as f64 + EPSILON, end.y() as f64 + EPSILON);
+    (0..=steps)
+        .map(|step| {
+            let t = step as f64 / steps as f64;
+            Coordinate::round(lerp(ax, bx, t), lerp(ay, by, t))
+        })
+        .collect()
+}
+
+/// the part of the line from `start` to `end` that can be seen from `start`, up to and including
+/// the first coordinate that `blocks`
+pub fn sight_line(
+    start: &Coordinate,
+    end: &Coordinate,
+    blocks: impl Fn(&Coordinate) -> bool,
+) -> Vec<Coordinate> {
+    let mut visible = vec![];
+    for (idx, coordinate) in line(start, end).into_iter().enumerate() {
+        visible.push(coordinate);
+        // the viewer's own coordinate never blocks
+        if idx > 0 && blocks(&coordinate) {
+            break;
+        }
+    }
+    visible
+}
+
+/// whether `end` can be seen from `start`, a blocking `end` itself is still visible
+pub fn line_of_sight(
+    start: &Coordinate,
+    end: &Coordinate,
+    blocks: impl Fn(&Coordinate) -> bool,
+) -> bool {
+    sight_line(start, end, blocks).last() == Some(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line() {
+        let start = Coordinate::new(-2, 1);
+        let end = Coordinate::new(3, -1);
+        let line = line(&start, &end);
+        assert_eq!(line.len() as u32, start.dist(&end) + 1);
+        assert_eq!(line.first(), Some(&start));
+        assert_eq!(line.last(), Some(&end));
+        // every step goes to a neighbor
+        assert!(line.windows(2).all(|pair| pair[0].dist(&pair[1]) == 1));
+        assert_eq!(
+            super::line(&Coordinate::new(0, 0), &Coordinate::new(3, 0)),
+            (0..=3).map(|x| Coordinate::new(x, 0)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_line_of_sight() {
+        let start = Coordinate::new(0, 0);
+        let mountain = Coordinate::new(2, 0);
+        let blocks = |coordinate: &Coordinate| coordinate == &mountain;
+        assert!(line_of_sight(&start, &mountain, blocks));
+        assert!(!line_of_sight(&start, &Coordinate::new(4, 0), blocks));
+        assert!(line_of_sight(&start, &Coordinate::new(0, 4), blocks));
+        assert_eq!(
+            sight_line(&start, &Coordinate::new(4, 0), blocks).last(),
+            Some(&mountain)
+        );
+    }
+}
@@ -1,8 +1,244 @@
+use crate::coordinate::line;
+use crate::coordinate::wrap::Wrap;
 use crate::coordinate::{Coordinate, Offset, ZERO};
-use std::collections::HashSet;
+use std::collections::btree_map::{self, BTreeMap};
+use std::fmt;
 use std::iter::FromIterator;
+use std::ops::{BitAnd, BitOr, Sub};
 
-pub type Range = HashSet<Coordinate>;
+/// a chunk covers 8x8 coordinates, one bit each
+const CHUNK_SHIFT: i32 = 3;
+const CHUNK_MASK: i32 = (1 << CHUNK_SHIFT) - 1;
+
+type ChunkKey = (i32, i32);
+
+fn chunk_of(coordinate: &Coordinate) -> (ChunkKey, u64) {
+    let key = (coordinate.x >> CHUNK_SHIFT, coordinate.y >> CHUNK_SHIFT);
+    let bit = ((coordinate.y & CHUNK_MASK) << CHUNK_SHIFT) | (coordinate.x & CHUNK_MASK);
+    (key, 1 << bit)
+}
+
+/// A set of coordinates, stored as a sparse map of 64 bit chunks. Set operations work a chunk at
+/// a time and iteration is ordered by chunk.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Range {
+    // never holds empty chunks, so equality is structural
+    chunks: BTreeMap<ChunkKey, u64>,
+}
+
+impl Range {
+    pub fn len(&self) -> usize {
+        self.chunks
+            .values()
+            .map(|bits| bits.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    pub fn contains(&self, coordinate: &Coordinate) -> bool {
+        let (key, bit) = chunk_of(coordinate);
+        self.chunks.get(&key).map_or(false, |bits| bits & bit != 0)
+    }
+
+    /// false if the coordinate was already in the range
+    pub fn insert(&mut self, coordinate: Coordinate) -> bool {
+        let (key, bit) = chunk_of(&coordinate);
+        let bits = self.chunks.entry(key).or_insert(0);
+        let inserted = *bits & bit == 0;
+        *bits |= bit;
+        inserted
+    }
+
+    /// false if the coordinate wasn't in the range
+    pub fn remove(&mut self, coordinate: &Coordinate) -> bool {
+        let (key, bit) = chunk_of(coordinate);
+        match self.chunks.get_mut(&key) {
+            Some(bits) if *bits & bit != 0 => {
+                *bits &= !bit;
+                if *bits == 0 {
+                    self.chunks.remove(&key);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            chunks: self.chunks.iter(),
+            current: None,
+        }
+    }
+
+    pub fn union(&self, other: &Range) -> Range {
+        let mut union = self.clone();
+        union.union_with(other);
+        union
+    }
+
+    pub fn intersection(&self, other: &Range) -> Range {
+        let chunks = self
+            .chunks
+            .iter()
+            .filter_map(|(key, bits)| {
+                let common = bits & other.chunks.get(key)?;
+                if common == 0 {
+                    None
+                } else {
+                    Some((*key, common))
+                }
+            })
+            .collect();
+        Range { chunks }
+    }
+
+    pub fn difference(&self, other: &Range) -> Range {
+        let mut difference = self.clone();
+        difference.difference_with(other);
+        difference
+    }
+
+    pub fn union_with(&mut self, other: &Range) {
+        for (key, bits) in other.chunks.iter() {
+            *self.chunks.entry(*key).or_insert(0) |= bits;
+        }
+    }
+
+    pub fn difference_with(&mut self, other: &Range) {
+        for (key, bits) in other.chunks.iter() {
+            if let Some(own) = self.chunks.get_mut(key) {
+                *own &= !bits;
+                if *own == 0 {
+                    self.chunks.remove(key);
+                }
+            }
+        }
+    }
+
+    pub fn is_disjoint(&self, other: &Range) -> bool {
+        self.chunks.iter().all(|(key, bits)| {
+            other
+                .chunks
+                .get(key)
+                .map_or(true, |other| bits & other == 0)
+        })
+    }
+
+    pub fn is_subset(&self, other: &Range) -> bool {
+        self.chunks.iter().all(|(key, bits)| {
+            other
+                .chunks
+                .get(key)
+                .map_or(false, |other| bits & !other == 0)
+        })
+    }
+}
+
+impl fmt::Debug for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+pub struct Iter<'a> {
+    chunks: btree_map::Iter<'a, ChunkKey, u64>,
+    current: Option<(ChunkKey, u64)>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Coordinate;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.current {
+                Some(((chunk_x, chunk_y), bits)) if bits != 0 => {
+                    let bit = bits.trailing_zeros() as i32;
+                    self.current = Some(((chunk_x, chunk_y), bits & (bits - 1)));
+                    return Some(Coordinate::new(
+                        (chunk_x << CHUNK_SHIFT) | (bit & CHUNK_MASK),
+                        (chunk_y << CHUNK_SHIFT) | (bit >> CHUNK_SHIFT),
+                    ));
+                }
+                _ => {
+                    let (key, bits) = self.chunks.next()?;
+                    self.current = Some((*key, *bits));
+                }
+            }
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Range {
+    type Item = Coordinate;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for Range {
+    type Item = Coordinate;
+    type IntoIter = std::vec::IntoIter<Coordinate>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter().collect::<Vec<_>>().into_iter()
+    }
+}
+
+impl FromIterator<Coordinate> for Range {
+    fn from_iter<T: IntoIterator<Item = Coordinate>>(iter: T) -> Self {
+        let mut range = Range::default();
+        range.extend(iter);
+        range
+    }
+}
+
+impl<'a> FromIterator<&'a Coordinate> for Range {
+    fn from_iter<T: IntoIterator<Item = &'a Coordinate>>(iter: T) -> Self {
+        iter.into_iter().copied().collect()
+    }
+}
+
+impl Extend<Coordinate> for Range {
+    fn extend<T: IntoIterator<Item = Coordinate>>(&mut self, iter: T) {
+        for coordinate in iter {
+            self.insert(coordinate);
+        }
+    }
+}
+
+impl BitOr for &Range {
+    type Output = Range;
+
+    fn bitor(self, rhs: &Range) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl BitAnd for &Range {
+    type Output = Range;
+
+    fn bitand(self, rhs: &Range) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+
+impl Sub for &Range {
+    type Output = Range;
+
+    fn sub(self, rhs: &Range) -> Self::Output {
+        self.difference(rhs)
+    }
+}
 
 const DIRECTIONS: [Coordinate; 6] = [
     Coordinate::new(1, -1),
@@ -25,15 +261,9 @@ pub trait RangeFactory {
         Range::from_iter(DIRECTIONS.iter().map(|d| coordinate + d))
     }
 
+    /// the coordinates at exactly `radius` from `center`, see `ring_coordinates` for them in order
     fn ring(center: &Coordinate, radius: u16) -> Range {
-        let mut results = vec![];
-        let i_radius = radius as i32;
-        for x in -i_radius..=i_radius {
-            for y in (-i_radius).max(-x - i_radius)..=i_radius.min(-x + i_radius) {
-                results.push(Coordinate::new(x, y) + *center)
-            }
-        }
-        Range::from_iter(results)
+        Range::from_iter(ring_coordinates(center, radius))
     }
 
     fn ring0(radius: u16) -> Range {
@@ -41,19 +271,16 @@ pub trait RangeFactory {
     }
 
     fn circle(center: &Coordinate, radius: u16) -> Range {
-        let mut results = vec![];
-        for sub_radius in 0..=radius {
-            results.extend(Range::ring(center, sub_radius).into_iter())
-        }
-        Range::from_iter(results)
+        Range::from_iter(spiral(center).take(spiral_len(radius)))
     }
 
     fn circle0(radius: u16) -> Range {
         Self::circle(&Default::default(), radius)
     }
 
-    fn line(_start: &Coordinate, _end: &Coordinate) -> Range {
-        unimplemented!()
+    /// see `line::line` for the coordinates in order
+    fn line(start: &Coordinate, end: &Coordinate) -> Range {
+        Range::from_iter(line::line(start, end))
     }
 
     fn line0(end: &Coordinate) -> Range {
@@ -82,10 +309,48 @@ pub trait RangeFactory {
     fn rectangle0(to_corner: &Coordinate) -> Range {
         Self::rectangle(&Default::default(), to_corner)
     }
+
+    /// `range` with every coordinate moved onto the grid, coordinates past the seam overlap
+    fn wrapped(range: &Range, wrap: &Wrap) -> Range {
+        range
+            .iter()
+            .map(|coordinate| wrap.normalize(&coordinate))
+            .collect()
+    }
 }
 
 impl RangeFactory for Range {}
 
+/// the ring around `center`, clockwise starting at the left
+pub fn ring_coordinates(center: &Coordinate, radius: u16) -> Vec<Coordinate> {
+    if radius == 0 {
+        return vec![*center];
+    }
+    let radius = radius as i32;
+    let mut coordinate =
+        center + Coordinate::new(DIRECTIONS[4].x * radius, DIRECTIONS[4].y * radius);
+    let mut ring = Vec::with_capacity(6 * radius as usize);
+    for direction in DIRECTIONS.iter() {
+        for _ in 0..radius {
+            ring.push(coordinate);
+            coordinate = coordinate + *direction;
+        }
+    }
+    ring
+}
+
+/// every coordinate ordered by distance from `center` outward, ring by ring
+pub fn spiral(center: &Coordinate) -> impl Iterator<Item = Coordinate> {
+    let center = *center;
+    (0..=u16::MAX).flat_map(move |radius| ring_coordinates(&center, radius))
+}
+
+/// how many coordinates a spiral up to and including `radius` has
+pub fn spiral_len(radius: u16) -> usize {
+    let radius = radius as usize;
+    1 + 3 * radius * (radius + 1)
+}
+
 pub trait RangeFrom {
     fn line_to(&self, end: &Coordinate) -> Range;
     fn circle(&self, radius: u16) -> Range;
@@ -110,3 +375,35 @@ impl RangeFrom for Coordinate {
         Range::rectangle(self, to_corner)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitset_range() {
+        let a = Range::circle0(3);
+        let b = Range::circle(&Coordinate::new(-9, 4), 3);
+        // spans chunks on both sides of 0
+        let coordinates: Vec<Coordinate> = a.union(&b).iter().collect();
+        assert_eq!(coordinates.len(), a.len() + b.len());
+        assert!(coordinates
+            .iter()
+            .all(|coordinate| a.contains(coordinate) || b.contains(coordinate)));
+
+        let c = Range::circle(&Coordinate::new(1, 0), 3);
+        assert_eq!(
+            (&a & &c).len() + (&a - &c).len(),
+            a.len(),
+            "intersection and difference partition the range"
+        );
+        assert!((&a - &c).is_disjoint(&c));
+        assert!((&a & &c).is_subset(&a));
+
+        let mut d = a.clone();
+        assert!(!d.insert(ZERO));
+        assert!(d.remove(&ZERO));
+        assert!(!d.contains(&ZERO));
+        assert_eq!(d.len(), a.len() - 1);
+    }
+}
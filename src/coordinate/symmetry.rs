@@ -0,0 +1,112 @@
+use crate::coordinate::range::Range;
+use crate::coordinate::Coordinate;
+
+/// the cube axis that stays fixed when reflecting
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+pub trait Symmetry {
+    /// rotate by `steps` times 60 degrees around `pivot`, positive steps turn clockwise
+    fn rotate(&self, pivot: &Coordinate, steps: i32) -> Self;
+    /// mirror along `axis` through `pivot`
+    fn reflect(&self, pivot: &Coordinate, axis: Axis) -> Self;
+}
+
+impl Symmetry for Coordinate {
+    fn rotate(&self, pivot: &Coordinate, steps: i32) -> Self {
+        let relative = self - pivot;
+        let (mut x, mut y, mut z) = (relative.x, relative.y, relative.z());
+        for _ in 0..steps.rem_euclid(6) {
+            let rotated = (-z, -x, -y);
+            x = rotated.0;
+            y = rotated.1;
+            z = rotated.2;
+        }
+        pivot + Coordinate::new(x, y)
+    }
+
+    fn reflect(&self, pivot: &Coordinate, axis: Axis) -> Self {
+        let relative = self - pivot;
+        let (x, y, z) = (relative.x, relative.y, relative.z());
+        let reflected = match axis {
+            Axis::X => Coordinate::new(x, z),
+            Axis::Y => Coordinate::new(z, y),
+            Axis::Z => Coordinate::new(y, x),
+        };
+        pivot + reflected
+    }
+}
+
+impl Symmetry for Range {
+    fn rotate(&self, pivot: &Coordinate, steps: i32) -> Self {
+        self.iter()
+            .map(|coordinate| coordinate.rotate(pivot, steps))
+            .collect()
+    }
+
+    fn reflect(&self, pivot: &Coordinate, axis: Axis) -> Self {
+        self.iter()
+            .map(|coordinate| coordinate.reflect(pivot, axis))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinate::dist::Dist;
+    use crate::coordinate::range::{ring_coordinates, spiral, spiral_len, RangeFactory};
+
+    #[test]
+    fn test_rings_and_spiral() {
+        let center = Coordinate::new(2, -1);
+        assert_eq!(Range::ring(&center, 0).len(), 1);
+        for radius in 1..4 {
+            let ring = ring_coordinates(&center, radius);
+            assert_eq!(ring.len(), 6 * radius as usize);
+            assert!(ring.iter().all(|c| c.dist(&center) == radius as u32));
+            // consecutive and wrapping around
+            assert!(ring.windows(2).all(|pair| pair[0].dist(&pair[1]) == 1));
+            assert_eq!(ring[0].dist(ring.last().unwrap()), 1);
+        }
+        let spiral: Vec<Coordinate> = spiral(&center).take(spiral_len(3)).collect();
+        assert!(spiral
+            .windows(2)
+            .all(|pair| pair[0].dist(&center) <= pair[1].dist(&center)));
+        assert_eq!(
+            spiral.into_iter().collect::<Range>(),
+            Range::circle(&center, 3)
+        );
+    }
+
+    #[test]
+    fn test_symmetry() {
+        let pivot = Coordinate::new(1, 1);
+        let coordinate = Coordinate::new(3, 0);
+        let orbit: Vec<Coordinate> = (0..6)
+            .map(|steps| coordinate.rotate(&pivot, steps))
+            .collect();
+        assert_eq!(coordinate.rotate(&pivot, 6), coordinate);
+        assert_eq!(coordinate.rotate(&pivot, -1), orbit[5]);
+        assert!(orbit
+            .iter()
+            .all(|c| c.dist(&pivot) == coordinate.dist(&pivot)));
+        assert_eq!(orbit.into_iter().collect::<Range>().len(), 6);
+        for axis in [Axis::X, Axis::Y, Axis::Z].iter() {
+            assert_eq!(
+                coordinate.reflect(&pivot, *axis).reflect(&pivot, *axis),
+                coordinate
+            );
+        }
+
+        let footprint = Range::new(&[Coordinate::new(1, 1), Coordinate::new(2, 1)]);
+        assert_eq!(
+            footprint.rotate(&pivot, 3),
+            Range::new(&[Coordinate::new(1, 1), Coordinate::new(0, 1)])
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use crate::coordinate::{Coordinate, Offset};
+
+/// A world that wraps along the longitude, so the east edge is the neighbor of the west edge.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Wrap {
+    columns: i32,
+}
+
+impl Wrap {
+    /// the offset rows only line up across the seam with an even number of columns, none otherwise
+    pub fn new(columns: usize) -> Option<Self> {
+        if columns == 0 || columns % 2 == 1 {
+            return None;
+        }
+        Some(Wrap {
+            columns: columns as i32,
+        })
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns as usize
+    }
+
+    /// going once around the world, moves the column by `columns` and keeps the row
+    pub fn circumference(&self) -> Coordinate {
+        Coordinate::new(self.columns, -self.columns / 2)
+    }
+
+    /// the same coordinate with its column within the grid, see `WithGrid::contains`
+    pub fn normalize(&self, coordinate: &Coordinate) -> Coordinate {
+        let offset: Offset = coordinate.into();
+        let laps = (offset.column() + self.columns / 2).div_euclid(self.columns);
+        let circumference = self.circumference();
+        Coordinate::new(
+            coordinate.x() - laps * circumference.x(),
+            coordinate.y() - laps * circumference.y(),
+        )
+    }
+
+    /// the copies of `coordinate` one lap to the west and east, for the shorter way around
+    pub fn neighboring_laps(&self, coordinate: &Coordinate) -> [Coordinate; 3] {
+        let circumference = self.circumference();
+        [
+            coordinate - circumference,
+            *coordinate,
+            coordinate + circumference,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinate::dist::Dist;
+    use crate::coordinate::range::{Range, RangeFactory};
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(Wrap::new(9), None);
+        assert_eq!(Wrap::new(0), None);
+        let wrap = Wrap::new(10).unwrap();
+        let west: Coordinate = Offset::new(-5, 2).into();
+        let east: Coordinate = Offset::new(4, 2).into();
+        assert_eq!(wrap.normalize(&Offset::new(5, 2).into()), west);
+        assert_eq!(wrap.normalize(&Offset::new(-6, 2).into()), east);
+        assert_eq!(wrap.normalize(&Offset::new(-25, 2).into()), west);
+        assert_eq!(wrap.normalize(&east), east);
+
+        assert_eq!(east.dist(&west), 9);
+        assert_eq!(east.dist_wrapped(&west, &wrap), 1);
+        let neighbors = Range::wrapped(&Range::neighbors(&east), &wrap);
+        assert!(neighbors.contains(&west));
+        assert_eq!(neighbors.len(), 6);
+    }
+}
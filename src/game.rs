@@ -1,11 +1,50 @@
+use crate::clock::calendar::{Calendar, DEFAULT_START_YEAR};
+use crate::clock::clock_driver::ClockDriver;
 use crate::clock::Clock;
+use crate::coordinate::wrap::Wrap;
+use crate::map::terrain::TerrainRules;
 use crate::map::Map;
+use crate::observable::event_bus::EventBus;
+use crate::observable::{Backpressure, Dispatch, Subscribed};
+use std::collections::hash_map::RandomState;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::SystemTime;
+use strum_macros::AsRefStr;
 
-#[derive(Copy, Clone)]
+/// the world every game had before seeds were configurable
+pub const DEFAULT_SEED: u32 = 1234;
+
+#[derive(Debug, AsRefStr)]
+pub enum ConfigurationError {
+    /// a wrapped longitude needs an even number of columns
+    OddColumns(usize),
+}
+
+impl fmt::Display for ConfigurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigurationError::OddColumns(columns) => write!(f, "{}: {}", self.as_ref(), columns),
+        }
+    }
+}
+
+impl Error for ConfigurationError {}
+
+#[derive(Clone)]
 pub struct Configuration {
     rows: usize,
     columns: usize,
     island_noise: f64,
+    dispatch: Dispatch,
+    clock_backpressure: Backpressure,
+    batched_events: bool,
+    ticks_per_day: usize,
+    wrapped_longitude: bool,
+    seed: u32,
+    terrain_rules: Arc<TerrainRules>,
 }
 
 impl Configuration {
@@ -14,6 +53,34 @@ impl Configuration {
             rows,
             columns,
             island_noise,
+            dispatch: Dispatch::default(),
+            clock_backpressure: Backpressure::default(),
+            batched_events: false,
+            ticks_per_day: 1,
+            wrapped_longitude: false,
+            seed: DEFAULT_SEED,
+            terrain_rules: Default::default(),
+        }
+    }
+
+    /// deliver all game events synchronously, e.g. for reproducible ticks in tests
+    pub fn with_dispatch(self, dispatch: Dispatch) -> Self {
+        Configuration { dispatch, ..self }
+    }
+
+    /// how ticks and tocks reach a slow observer, they block the clock by default
+    pub fn with_clock_backpressure(self, clock_backpressure: Backpressure) -> Self {
+        Configuration {
+            clock_backpressure,
+            ..self
+        }
+    }
+
+    /// deliver the territory and building changes of a construction as one event
+    pub fn with_batched_events(self, batched_events: bool) -> Self {
+        Configuration {
+            batched_events,
+            ..self
         }
     }
 
@@ -28,27 +95,125 @@ impl Configuration {
     pub fn island_noise(&self) -> f64 {
         self.island_noise
     }
+
+    pub fn with_ticks_per_day(self, ticks_per_day: usize) -> Self {
+        Configuration {
+            ticks_per_day,
+            ..self
+        }
+    }
+
+    /// let the east and west edge meet, the number of columns has to be even
+    pub fn with_wrapped_longitude(
+        self,
+        wrapped_longitude: bool,
+    ) -> Result<Self, ConfigurationError> {
+        if wrapped_longitude && Wrap::new(self.columns).is_none() {
+            return Err(ConfigurationError::OddColumns(self.columns));
+        }
+        Ok(Configuration {
+            wrapped_longitude,
+            ..self
+        })
+    }
+
+    /// the same seed always generates the same world
+    pub fn with_seed(self, seed: u32) -> Self {
+        Configuration { seed, ..self }
+    }
+
+    /// a seed from e.g. a name the player typed in, FNV-1a so it is the same on every platform
+    pub fn with_seed_from(self, text: &str) -> Self {
+        let seed = text.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+        });
+        self.with_seed(seed)
+    }
+
+    /// a new world every time, `seed()` tells which one to play it again
+    pub fn with_random_seed(self) -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        if let Ok(elapsed) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            hasher.write_u128(elapsed.as_nanos());
+        }
+        self.with_seed(hasher.finish() as u32)
+    }
+
+    /// e.g. loaded with `TerrainRules::load`, the built-in rules otherwise
+    pub fn with_terrain_rules(self, terrain_rules: TerrainRules) -> Self {
+        Configuration {
+            terrain_rules: Arc::new(terrain_rules),
+            ..self
+        }
+    }
+
+    pub fn dispatch(&self) -> Dispatch {
+        self.dispatch
+    }
+
+    pub fn clock_backpressure(&self) -> Backpressure {
+        self.clock_backpressure
+    }
+
+    pub fn batched_events(&self) -> bool {
+        self.batched_events
+    }
+
+    pub fn ticks_per_day(&self) -> usize {
+        self.ticks_per_day
+    }
+
+    pub fn wrapped_longitude(&self) -> bool {
+        self.wrapped_longitude
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn terrain_rules(&self) -> Arc<TerrainRules> {
+        self.terrain_rules.clone()
+    }
 }
 
 pub struct Game {
     configuration: Configuration,
-    clock: Clock,
+    clock: Arc<Clock>,
+    clock_driver: ClockDriver,
+    calendar: Subscribed<Calendar>,
     map: Map,
+    events: EventBus,
 }
 
 impl Game {
     pub fn new(configuration: Configuration) -> Self {
-        let clock = Clock::new();
-        Game {
+        let clock = Arc::new(Clock::with_backpressure(
+            configuration.dispatch,
+            configuration.clock_backpressure,
+        ));
+        let game = Game {
+            map: Map::new(&clock, &configuration),
+            clock_driver: ClockDriver::new(&clock),
+            calendar: Calendar::new(
+                configuration.ticks_per_day,
+                DEFAULT_START_YEAR,
+                configuration.dispatch,
+            )
+            .subscribed(&clock),
             configuration,
-            map: Map::new(
-                &clock,
-                configuration.rows,
-                configuration.columns,
-                configuration.island_noise,
-            ),
             clock,
-        }
+            events: EventBus::new(),
+        };
+        game.route_events();
+        game
+    }
+
+    fn route_events(&self) {
+        self.events.route(self.clock.tickers());
+        self.events.route(self.clock.tockers());
+        self.events.route(self.calendar.days());
+        self.events.route(self.calendar.seasons());
+        self.map.route_events(&self.events);
     }
 
     pub fn configuration(&self) -> &Configuration {
@@ -62,15 +227,30 @@ impl Game {
     pub fn clock(&self) -> &Clock {
         &self.clock
     }
+
+    pub fn clock_driver(&self) -> &ClockDriver {
+        &self.clock_driver
+    }
+
+    pub fn calendar(&self) -> &Calendar {
+        &self.calendar
+    }
+
+    /// every game event, no matter where it is emitted
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use strum::EnumCount;
 
-    use crate::coordinate::Coordinate;
-    use crate::map::minimap::GetByCoordinate;
+    use crate::coordinate::{Coordinate, Offset};
+    use crate::map::buildings::buildings_controller::ConstructionError;
+    use crate::map::minimap::{GetByCoordinate, WithGrid};
     use crate::map::terrain::{TerrainMeta, TerrainType};
+    use crate::tile::TileName;
 
     use super::*;
 
@@ -83,4 +263,59 @@ mod tests {
         let terrain_meta: TerrainMeta = game.map().terrain().get(&coordinate);
         assert!(terrain_meta.moisture() >= 0.);
     }
+
+    #[test]
+    fn test_seed() {
+        let configuration = || Configuration::new(20, 20, 4.);
+        assert_eq!(configuration().seed(), DEFAULT_SEED);
+        assert_eq!(
+            configuration().with_seed_from("Ultreia").seed(),
+            configuration().with_seed_from("Ultreia").seed()
+        );
+        assert_ne!(
+            configuration().with_seed_from("Ultreia").seed(),
+            configuration().with_seed_from("Santiago").seed()
+        );
+
+        let terrain_types = |configuration: Configuration| -> Vec<TerrainType> {
+            let game = Game::new(configuration);
+            let terrain = game.map().terrain();
+            terrain
+                .coordinates()
+                .iter()
+                .map(|coordinate| terrain.get(&coordinate))
+                .collect()
+        };
+        let random = configuration().with_random_seed();
+        let seed = random.seed();
+        assert!(terrain_types(random) == terrain_types(configuration().with_seed(seed)));
+        assert!(
+            terrain_types(configuration().with_seed(1))
+                != terrain_types(configuration().with_seed(2))
+        );
+    }
+
+    #[test]
+    fn test_construct_out_of_bounds() {
+        let game = Game::new(Configuration::new(10, 10, 4.));
+        let result = game
+            .map()
+            .buildings_controller()
+            .try_construct(Offset::new(5, 0).into(), &TileName::Warehouse);
+        assert!(matches!(result, Err(ConstructionError::OutOfBounds)));
+    }
+    #[test]
+    fn test_wrapped_longitude() {
+        assert!(Configuration::new(10, 10, 4.)
+            .with_wrapped_longitude(true)
+            .unwrap()
+            .wrapped_longitude());
+        assert!(matches!(
+            Configuration::new(10, 9, 4.).with_wrapped_longitude(true),
+            Err(ConfigurationError::OddColumns(9))
+        ));
+        assert!(Configuration::new(10, 9, 4.)
+            .with_wrapped_longitude(false)
+            .is_ok());
+    }
 }
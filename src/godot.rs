@@ -5,7 +5,10 @@ mod emit_deferred;
 mod fow;
 mod game;
 mod game_controller;
+mod globalize_path;
 mod good;
+mod hex_layout;
+mod journal;
 mod terrain;
 mod territory;
 mod variant;
@@ -15,6 +18,8 @@ use crate::godot::clock::Clock;
 use crate::godot::fow::FOW;
 use crate::godot::game::Game;
 use crate::godot::good::Good;
+use crate::godot::hex_layout::HexLayout;
+use crate::godot::journal::Journal;
 use crate::godot::terrain::Terrain;
 use crate::godot::territory::Territory;
 use gdnative::prelude::*;
@@ -28,6 +33,8 @@ fn init(handle: InitHandle) {
     handle.add_class::<FOW>();
     handle.add_class::<Territory>();
     handle.add_class::<Buildings>();
+    handle.add_class::<HexLayout>();
+    handle.add_class::<Journal>();
 }
 
 // create entry points for library
@@ -7,14 +7,14 @@ use crate::godot::buildings::buildings_signal::{BuildingsObserver, BuildingsSign
 use crate::godot::game::GameSignal;
 use crate::godot::game_controller::GameController;
 use crate::map::buildings::buildings_controller::ConstructionError;
+use crate::observable::Subscribed;
 use crate::tile::TileName;
-use std::sync::Arc;
 
 #[derive(NativeClass)]
 #[inherit(Node)]
 #[register_with(Self::register_signals)]
 pub struct Buildings {
-    buildings_observer: Option<Arc<BuildingsObserver>>,
+    buildings_observer: Option<Subscribed<BuildingsObserver>>,
 }
 
 impl Buildings {
@@ -76,7 +76,7 @@ impl Buildings {
     fn _attach_game(&mut self, owner: TRef<Node>) {
         godot_print!("attaching clock to game now");
         let game = GameController::game().expect("game should be here");
-        let buildings_observer = BuildingsObserver::new(&game.map().buildings(), owner.claim());
+        let buildings_observer = BuildingsObserver::new(game.events(), owner.claim());
         self.buildings_observer.replace(buildings_observer);
     }
 
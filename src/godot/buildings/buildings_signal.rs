@@ -1,8 +1,8 @@
 use crate::godot::emit_deferred::EmitDeferred;
-use crate::map::buildings::{BuildingCreated, BuildingDestroyed, Buildings};
-use crate::observable::{Observable, Observer};
+use crate::map::buildings::{BuildingCreated, BuildingDestroyed, BuildingsChanged};
+use crate::observable::event_bus::EventBus;
+use crate::observable::{Observer, Subscribed};
 use gdnative::prelude::*;
-use std::sync::Arc;
 use strum_macros::AsRefStr;
 
 #[derive(Copy, Clone, PartialEq, Eq, AsRefStr)]
@@ -45,11 +45,24 @@ impl Observer<BuildingDestroyed> for BuildingsObserver {
     }
 }
 
+impl Observer<BuildingsChanged> for BuildingsObserver {
+    fn notify(&self, event: &BuildingsChanged) {
+        event
+            .created
+            .iter()
+            .for_each(|created| self.notify(created));
+        event
+            .destroyed
+            .iter()
+            .for_each(|destroyed| self.notify(destroyed));
+    }
+}
+
 impl BuildingsObserver {
-    pub fn new(buildings: &Buildings, owner: Ref<Node, Shared>) -> Arc<Self> {
-        let observer = Arc::new(Self { owner });
-        Observable::<BuildingCreated>::observers(buildings).register(&observer);
-        Observable::<BuildingDestroyed>::observers(buildings).register(&observer);
-        observer
+    pub fn new(events: &EventBus, owner: Ref<Node, Shared>) -> Subscribed<Self> {
+        Subscribed::new(Self { owner })
+            .subscribe(&events.observers::<BuildingCreated>())
+            .subscribe(&events.observers::<BuildingDestroyed>())
+            .subscribe(&events.observers::<BuildingsChanged>())
     }
 }
@@ -2,17 +2,19 @@ mod clock_signal;
 
 use gdnative::prelude::*;
 
+use crate::clock::clock_driver::MAX_INTERVAL;
 use crate::godot::clock::clock_signal::ClockObserver;
 pub use crate::godot::clock::clock_signal::ClockSignal;
 use crate::godot::game::GameSignal;
 use crate::godot::game_controller::GameController;
-use std::sync::Arc;
+use crate::observable::Subscribed;
+use std::time::Duration;
 
 #[derive(NativeClass)]
 #[inherit(Node)]
 #[register_with(Self::register_signals)]
 pub struct Clock {
-    clock_observer: Option<Arc<ClockObserver>>,
+    clock_observer: Option<Subscribed<ClockObserver>>,
 }
 
 impl Clock {
@@ -74,4 +76,53 @@ impl Clock {
     fn tick(&self, _owner: &Node) -> Option<()> {
         Some(GameController::game()?.clock().tick())
     }
+
+    #[export]
+    fn pause(&self, _owner: &Node) -> Option<()> {
+        Some(GameController::game()?.clock_driver().pause())
+    }
+
+    #[export]
+    fn resume(&self, _owner: &Node) -> Option<()> {
+        Some(GameController::game()?.clock_driver().resume())
+    }
+
+    #[export]
+    fn is_paused(&self, _owner: &Node) -> Option<bool> {
+        Some(GameController::game()?.clock_driver().is_paused())
+    }
+
+    #[export]
+    fn step(&self, _owner: &Node) -> Option<()> {
+        Some(GameController::game()?.clock_driver().step())
+    }
+
+    #[export]
+    fn speed(&self, _owner: &Node) -> Option<f64> {
+        Some(GameController::game()?.clock_driver().speed())
+    }
+
+    #[export]
+    fn set_speed(&self, _owner: &Node, speed: f64) -> Option<()> {
+        if !speed.is_finite() {
+            godot_error!("the clock speed has to be finite, not {}", speed);
+            return None;
+        }
+        Some(GameController::game()?.clock_driver().set_speed(speed))
+    }
+
+    /// `seconds` outside of the driver's interval range are clamped to it
+    #[export]
+    fn set_interval(&self, _owner: &Node, seconds: f64) -> Option<()> {
+        if !seconds.is_finite() {
+            godot_error!("the clock interval has to be finite, not {}", seconds);
+            return None;
+        }
+        let interval = Duration::from_secs_f64(seconds.clamp(0., MAX_INTERVAL.as_secs_f64()));
+        Some(
+            GameController::game()?
+                .clock_driver()
+                .set_interval(interval),
+        )
+    }
 }
@@ -1,8 +1,7 @@
 use crate::clock::{Clock, Tick, Tock};
 use crate::godot::emit_deferred::EmitDeferred;
-use crate::observable::Observer;
+use crate::observable::{Observer, Subscribed};
 use gdnative::prelude::*;
-use std::sync::Arc;
 use strum_macros::AsRefStr;
 
 #[derive(Copy, Clone, PartialEq, Eq, AsRefStr)]
@@ -46,10 +45,9 @@ impl Observer<Tock> for ClockObserver {
 }
 
 impl ClockObserver {
-    pub fn new(clock: &Clock, owner: Ref<Node, Shared>) -> Arc<Self> {
-        let observer = Arc::new(ClockObserver { owner });
-        clock.tickers().register(&observer);
-        clock.tockers().register(&observer);
-        observer
+    pub fn new(clock: &Clock, owner: Ref<Node, Shared>) -> Subscribed<Self> {
+        Subscribed::new(ClockObserver { owner })
+            .subscribe(clock.tickers())
+            .subscribe(clock.tockers())
     }
 }
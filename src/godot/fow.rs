@@ -7,13 +7,13 @@ use crate::godot::fow::fow_signal::{FOWObserver, FOWSignal};
 use crate::godot::game::GameSignal;
 use crate::godot::game_controller::GameController;
 use crate::map::minimap::{GetByCoordinate, Minimap};
-use std::sync::Arc;
+use crate::observable::Subscribed;
 
 #[derive(NativeClass)]
 #[inherit(Node)]
 #[register_with(Self::register_signals)]
 pub struct FOW {
-    fow_observer: Option<Arc<FOWObserver>>,
+    fow_observer: Option<Subscribed<FOWObserver>>,
 }
 
 impl FOW {
@@ -56,7 +56,7 @@ impl FOW {
     fn _attach_game(&mut self, owner: TRef<Node>) {
         godot_print!("attaching clock to game now");
         let game = GameController::game().expect("game should be here");
-        let fow_observer = FOWObserver::new(&game.map().fow(), owner.claim());
+        let fow_observer = FOWObserver::new(game.events(), owner.claim());
         self.fow_observer.replace(fow_observer);
     }
 
@@ -1,9 +1,8 @@
 use crate::godot::emit_deferred::EmitDeferred;
-use crate::map::fow::{Uncover, FOW};
-use crate::observable::Observable;
-use crate::observable::Observer;
+use crate::map::fow::Uncover;
+use crate::observable::event_bus::EventBus;
+use crate::observable::{Observer, Subscribed};
 use gdnative::prelude::*;
-use std::sync::Arc;
 use strum_macros::AsRefStr;
 
 #[derive(Copy, Clone, PartialEq, Eq, AsRefStr)]
@@ -31,9 +30,7 @@ impl Observer<Uncover> for FOWObserver {
 }
 
 impl FOWObserver {
-    pub fn new(fow: &FOW, owner: Ref<Node, Shared>) -> Arc<Self> {
-        let observer = Arc::new(FOWObserver { owner });
-        fow.observers().register(&observer);
-        observer
+    pub fn new(events: &EventBus, owner: Ref<Node, Shared>) -> Subscribed<Self> {
+        Subscribed::new(FOWObserver { owner }).subscribe(&events.observers::<Uncover>())
     }
 }
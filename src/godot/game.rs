@@ -45,6 +45,6 @@ impl Game {
 
     #[export]
     fn configuration(&self, _owner: &Node) -> Option<Configuration> {
-        Some(*GameController::game()?.configuration())
+        Some(GameController::game()?.configuration().clone())
     }
 }
@@ -0,0 +1,8 @@
+use gdnative::api::ProjectSettings;
+
+/// `res://` and `user://` paths as the file system knows them, other paths stay as they are
+pub fn globalize_path(path: &str) -> String {
+    ProjectSettings::godot_singleton()
+        .globalize_path(path)
+        .to_string()
+}
@@ -0,0 +1,60 @@
+use gdnative::prelude::*;
+
+use crate::coordinate::layout::{Layout, Orientation};
+use crate::coordinate::Coordinate;
+
+/// the hex size the scenes are drawn with, see `HexGrid.gd`
+const DEFAULT_HEX_SIZE: (f64, f64) = (32., 28.);
+
+/// The conversions of a single hex grid, see `HexGrid.gd`.
+#[derive(NativeClass)]
+#[inherit(Reference)]
+pub struct HexLayout {
+    layout: Layout,
+}
+
+impl HexLayout {
+    fn new(_owner: &Reference) -> Self {
+        HexLayout {
+            layout: Layout::with_hex_size(Orientation::Flat, DEFAULT_HEX_SIZE, (0., 0.)),
+        }
+    }
+}
+
+#[methods]
+impl HexLayout {
+    #[export]
+    fn configure(&mut self, _owner: &Reference, hex_size: Vector2, origin: Vector2, pointy: bool) {
+        let orientation = if pointy {
+            Orientation::Pointy
+        } else {
+            Orientation::Flat
+        };
+        self.layout = Layout::with_hex_size(
+            orientation,
+            (hex_size.x as f64, hex_size.y as f64),
+            (origin.x as f64, origin.y as f64),
+        );
+    }
+
+    #[export]
+    fn center(&self, _owner: &Reference, coordinate: Coordinate) -> Vector2 {
+        let (x, y) = self.layout.center(&coordinate);
+        Vector2::new(x as f32, y as f32)
+    }
+
+    #[export]
+    fn coordinate_at(&self, _owner: &Reference, position: Vector2) -> Coordinate {
+        self.layout
+            .coordinate_at((position.x as f64, position.y as f64))
+    }
+
+    #[export]
+    fn corners(&self, _owner: &Reference, coordinate: Coordinate) -> Vec<Vector2> {
+        self.layout
+            .corners(&coordinate)
+            .iter()
+            .map(|(x, y)| Vector2::new(*x as f32, *y as f32))
+            .collect()
+    }
+}
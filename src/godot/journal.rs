@@ -0,0 +1,108 @@
+use gdnative::prelude::*;
+
+use crate::godot::game_controller::GameController;
+use crate::godot::globalize_path::globalize_path;
+use crate::journal::{Recorder, Replayer};
+use crate::observable::{Dispatch, Subscribed};
+
+/// Records the running game, or replays a recording to the other nodes instead.
+#[derive(NativeClass)]
+#[inherit(Node)]
+pub struct Journal {
+    recorder: Option<Subscribed<Recorder>>,
+    replayer: Option<Replayer>,
+    // whether the clock was running before the replay paused it
+    resume_after_replay: bool,
+}
+
+impl Journal {
+    fn new(_owner: &Node) -> Self {
+        Journal {
+            recorder: None,
+            replayer: None,
+            resume_after_replay: false,
+        }
+    }
+}
+
+#[methods]
+impl Journal {
+    #[export]
+    fn record(&mut self, _owner: &Node, path: String) -> bool {
+        let game = match GameController::game() {
+            Some(game) => game,
+            None => return false,
+        };
+        match Recorder::create(globalize_path(&path)) {
+            Ok(recorder) => {
+                self.recorder.replace(recorder.record(&game));
+                true
+            }
+            Err(error) => {
+                godot_error!("could not record to {}: {}", path, error);
+                false
+            }
+        }
+    }
+
+    #[export]
+    fn stop_recording(&mut self, _owner: &Node) {
+        if let Some(recorder) = self.recorder.take() {
+            if let Err(error) = recorder.flush() {
+                godot_error!("could not finish the recording: {}", error);
+            }
+        }
+    }
+
+    /// the events of the recording go to the signals of the other nodes, step through them with
+    /// `replay_step`. The clock is paused until `stop_replay`, so the running game doesn't mix its
+    /// own events into them.
+    #[export]
+    fn replay(&mut self, _owner: &Node, path: String) -> bool {
+        let game = match GameController::game() {
+            Some(game) => game,
+            None => return false,
+        };
+        match Replayer::open(globalize_path(&path), Dispatch::Synchronous) {
+            Ok(replayer) => {
+                if self.replayer.is_none() {
+                    self.resume_after_replay = !game.clock_driver().is_paused();
+                }
+                game.clock_driver().pause();
+                replayer.route(game.events());
+                self.replayer.replace(replayer);
+                true
+            }
+            Err(error) => {
+                godot_error!("could not replay {}: {}", path, error);
+                false
+            }
+        }
+    }
+
+    /// replay the next epoch, returns it or nothing at the end of the recording
+    #[export]
+    fn replay_step(&mut self, _owner: &Node) -> Option<usize> {
+        self.replayer.as_mut()?.step()
+    }
+
+    #[export]
+    fn replay_all(&mut self, _owner: &Node) {
+        if let Some(replayer) = self.replayer.as_mut() {
+            replayer.replay_all();
+        }
+    }
+
+    /// stop replaying and resume the clock if it was running before
+    #[export]
+    fn stop_replay(&mut self, _owner: &Node) {
+        if self.replayer.take().is_none() {
+            return;
+        }
+        if self.resume_after_replay {
+            if let Some(game) = GameController::game() {
+                game.clock_driver().resume();
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use gdnative::prelude::*;
 
+use crate::coordinate::faces::Faces;
+use crate::coordinate::range::{Range, RangeFactory};
 use crate::coordinate::Coordinate;
 use crate::godot::game_controller::GameController;
 use crate::map::minimap::{GetByCoordinate, Minimap};
@@ -54,6 +56,33 @@ impl Terrain {
         Some(GameController::game()?.map().terrain().get(&coordinate))
     }
 
+    /// the width of the rivers along `coordinate`, by the neighbor on the other side of them
+    #[export]
+    fn rivers(&self, _owner: &Node, coordinate: Coordinate) -> Option<Dictionary> {
+        let faces = GameController::game()?
+            .map()
+            .terrain()
+            .rivers()
+            .faces(&coordinate);
+        let dict = Dictionary::new();
+        for (face, river) in faces {
+            if let Some(neighbor) = coordinate.neighbor_at(&face) {
+                dict.insert(neighbor.to_variant(), river.width() as i64);
+            }
+        }
+        Some(dict.into_shared())
+    }
+
+    /// generate the terrain of the rectangle between the corners in parallel, before it is shown
+    #[export]
+    fn prefetch(&self, _owner: &Node, from_corner: Coordinate, to_corner: Coordinate) {
+        if let Some(game) = GameController::game() {
+            game.map()
+                .terrain()
+                .prefetch(&Range::rectangle(&from_corner, &to_corner));
+        }
+    }
+
     #[export]
     fn minimap(&self, _owner: &Node, width: u16, height: u16) -> Option<Vec<TerrainType>> {
         Some(
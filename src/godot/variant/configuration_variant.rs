@@ -1,4 +1,6 @@
 use crate::game::Configuration;
+use crate::godot::globalize_path::globalize_path;
+use crate::map::terrain::TerrainRules;
 use gdnative::core_types::{Dictionary, FromVariant, FromVariantError, ToVariant, Variant};
 
 impl ToVariant for Configuration {
@@ -7,6 +9,8 @@ impl ToVariant for Configuration {
         dict.insert("rows", self.rows());
         dict.insert("columns", self.columns());
         dict.insert("island_noise", self.island_noise());
+        dict.insert("wrapped_longitude", self.wrapped_longitude());
+        dict.insert("seed", self.seed());
         Variant::from_dictionary(&dict.into_shared())
     }
 }
@@ -17,7 +21,28 @@ impl FromVariant for Configuration {
             let rows = dict.get("rows").to_u64() as usize;
             let columns = dict.get("columns").to_u64() as usize;
             let island_noise = dict.get("island_noise").to_f64();
-            Ok(Configuration::new(rows, columns, island_noise))
+            let wrapped_longitude = dict.get("wrapped_longitude").to_bool();
+            let mut configuration = Configuration::new(rows, columns, island_noise)
+                .with_wrapped_longitude(wrapped_longitude)
+                .map_err(|error| FromVariantError::custom(error.to_string()))?;
+            // a path to a file like src/map/terrain/terrain_rules.ron, also in res:// or user://,
+            // the built-in rules otherwise
+            let terrain_rules = dict.get("terrain_rules");
+            if !terrain_rules.is_nil() {
+                let rules = TerrainRules::load(globalize_path(&terrain_rules.to_string()))
+                    .map_err(|error| FromVariantError::custom(error.to_string()))?;
+                configuration = configuration.with_terrain_rules(rules);
+            }
+            // an explicit seed wins over one from text, without either the world is random
+            let seed = dict.get("seed");
+            let seed_text = dict.get("seed_text");
+            Ok(if !seed.is_nil() {
+                configuration.with_seed(seed.to_u64() as u32)
+            } else if !seed_text.is_nil() {
+                configuration.with_seed_from(&seed_text.to_string())
+            } else {
+                configuration.with_random_seed()
+            })
         } else {
             Err(FromVariantError::custom(
                 "could not convert variant into a TerrainTile",
@@ -1,5 +1,5 @@
 use crate::godot::variant::make_dict::make_dict;
-use crate::map::terrain::{Elevation, Moisture, TerrainMeta};
+use crate::map::terrain::{Elevation, Moisture, Temperature, TerrainMeta};
 use gdnative::core_types::{Dictionary, ToVariant, Variant};
 
 impl ToVariant for Elevation {
@@ -14,11 +14,18 @@ impl ToVariant for Moisture {
     }
 }
 
+impl ToVariant for Temperature {
+    fn to_variant(&self) -> Variant {
+        Variant::from_f64((*self).into())
+    }
+}
+
 impl ToVariant for TerrainMeta {
     fn to_variant(&self) -> Variant {
         let dict = Dictionary::new();
         dict.insert("elevation", self.elevation());
         dict.insert("moisture", self.moisture());
+        dict.insert("temperature", self.temperature());
         dict.insert("terrain_type", self.terrain_type().to_variant());
         dict.insert("yields", make_dict(self.yields()));
         Variant::from_dictionary(&dict.into_shared())
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use strum_macros::{AsRefStr, Display, EnumCount, EnumIter, IntoStaticStr};
 
 pub use self::inventory::{Inventory, InventoryAmount, SpecializedInventory, WithFromInventory};
@@ -9,6 +10,7 @@ macro_rules! make_good {
     ($name:tt, default $default:tt, $($arg:tt),+) => {
         #[derive(
             Debug, Display, Copy, Clone, PartialEq, Eq, Hash, AsRefStr, IntoStaticStr, EnumIter, EnumCount,
+            Serialize, Deserialize,
         )]
         pub enum $name {
             $($arg),+
@@ -0,0 +1,543 @@
+use crate::clock::calendar::{DayChanged, SeasonChanged};
+use crate::clock::{Phase, Tick, Tock};
+use crate::game::Game;
+use crate::map::buildings::{BuildingCreated, BuildingDestroyed, BuildingsChanged};
+use crate::map::fow::Uncover;
+use crate::map::territories::{TerritoriesChanged, TerritoryJoined, TerritoryLeft};
+use crate::observable::event_bus::EventBus;
+use crate::observable::{Dispatch, Observable, Observer, Observers, Subscribed};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use strum_macros::AsRefStr;
+
+/// every journal starts with this, followed by the format version, new events are only appended
+/// to `JournalEvent` so older journals stay readable
+const MAGIC: &[u8; 4] = b"ULTJ";
+const VERSION: u8 = 1;
+
+#[derive(Debug, AsRefStr)]
+pub enum JournalError {
+    Io(io::Error),
+    Encoding(bincode::Error),
+    /// missing magic bytes or an unknown version
+    NotAJournal,
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalError::Io(error) => write!(f, "{}: {}", self.as_ref(), error),
+            JournalError::Encoding(error) => write!(f, "{}: {}", self.as_ref(), error),
+            JournalError::NotAJournal => write!(f, "{}", self.as_ref()),
+        }
+    }
+}
+
+impl Error for JournalError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            JournalError::Io(error) => Some(error),
+            JournalError::Encoding(error) => Some(error),
+            JournalError::NotAJournal => None,
+        }
+    }
+}
+
+impl From<io::Error> for JournalError {
+    fn from(error: io::Error) -> Self {
+        JournalError::Io(error)
+    }
+}
+
+impl From<bincode::Error> for JournalError {
+    fn from(error: bincode::Error) -> Self {
+        JournalError::Encoding(error)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JournalEvent {
+    Tick(Tick),
+    Tock(Tock),
+    Uncover(Uncover),
+    TerritoryJoined(TerritoryJoined),
+    TerritoryLeft(TerritoryLeft),
+    TerritoriesChanged(TerritoriesChanged),
+    BuildingCreated(BuildingCreated),
+    BuildingDestroyed(BuildingDestroyed),
+    BuildingsChanged(BuildingsChanged),
+    DayChanged(DayChanged),
+    SeasonChanged(SeasonChanged),
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub epoch: usize,
+    pub event: JournalEvent,
+}
+
+/// Writes every observable game event to a journal. Epochs are only exact with synchronous
+/// dispatch, threaded events are stamped with the epoch they are delivered in.
+pub struct Recorder {
+    writer: Mutex<Box<dyn Write + Send>>,
+    epoch: AtomicUsize,
+    failed: AtomicBool,
+}
+
+impl Recorder {
+    pub fn new(mut writer: impl Write + Send + 'static) -> Result<Self, JournalError> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(Recorder {
+            writer: Mutex::new(Box::new(writer)),
+            epoch: AtomicUsize::new(0),
+            failed: AtomicBool::new(false),
+        })
+    }
+
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, JournalError> {
+        Recorder::new(BufWriter::new(File::create(path)?))
+    }
+
+    /// start recording the events of `game`, recording stops when the subscription is dropped
+    pub fn record(self, game: &Game) -> Subscribed<Self> {
+        let clock = game.clock();
+        let calendar = game.calendar();
+        let map = game.map();
+        let fow = map.fow();
+        let territories = map.territories();
+        let buildings = map.buildings();
+        Subscribed::new(self)
+            .subscribe(clock.phase(Phase::PreTick))
+            .subscribe(clock.tockers())
+            .subscribe(calendar.days())
+            .subscribe(calendar.seasons())
+            .subscribe(Observable::<Uncover>::observers(&*fow))
+            .subscribe(Observable::<TerritoryJoined>::observers(&*territories))
+            .subscribe(Observable::<TerritoryLeft>::observers(&*territories))
+            .subscribe(Observable::<TerritoriesChanged>::observers(&*territories))
+            .subscribe(Observable::<BuildingCreated>::observers(&*buildings))
+            .subscribe(Observable::<BuildingDestroyed>::observers(&*buildings))
+            .subscribe(Observable::<BuildingsChanged>::observers(&*buildings))
+    }
+
+    pub fn flush(&self) -> Result<(), JournalError> {
+        Ok(self.writer.lock().unwrap().flush()?)
+    }
+
+    fn write(&self, event: JournalEvent) {
+        self.write_at(self.epoch.load(Ordering::Acquire), event);
+    }
+
+    fn write_at(&self, epoch: usize, event: JournalEvent) {
+        let entry = Entry { epoch, event };
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(error) = bincode::serialize_into(&mut *writer, &entry) {
+            // only complain once, a broken journal tends to stay broken
+            if !self.failed.swap(true, Ordering::Relaxed) {
+                warn!("could not write journal entry: {}", error);
+            }
+        }
+    }
+}
+
+impl Observer<Tick> for Recorder {
+    fn notify(&self, tick: &Tick) {
+        self.epoch.store(tick.epoch(), Ordering::Release);
+        self.write(JournalEvent::Tick(*tick));
+    }
+}
+
+impl Observer<Tock> for Recorder {
+    fn notify(&self, tock: &Tock) {
+        self.write(JournalEvent::Tock(*tock));
+    }
+}
+
+impl Observer<Uncover> for Recorder {
+    fn notify(&self, event: &Uncover) {
+        self.write(JournalEvent::Uncover(event.clone()));
+    }
+}
+
+impl Observer<TerritoryJoined> for Recorder {
+    fn notify(&self, event: &TerritoryJoined) {
+        self.write(JournalEvent::TerritoryJoined(*event));
+    }
+}
+
+impl Observer<TerritoryLeft> for Recorder {
+    fn notify(&self, event: &TerritoryLeft) {
+        self.write(JournalEvent::TerritoryLeft(*event));
+    }
+}
+
+impl Observer<TerritoriesChanged> for Recorder {
+    fn notify(&self, event: &TerritoriesChanged) {
+        self.write(JournalEvent::TerritoriesChanged(event.clone()));
+    }
+}
+
+impl Observer<BuildingCreated> for Recorder {
+    fn notify(&self, event: &BuildingCreated) {
+        self.write(JournalEvent::BuildingCreated(*event));
+    }
+}
+
+impl Observer<BuildingDestroyed> for Recorder {
+    fn notify(&self, event: &BuildingDestroyed) {
+        self.write(JournalEvent::BuildingDestroyed(*event));
+    }
+}
+
+impl Observer<BuildingsChanged> for Recorder {
+    fn notify(&self, event: &BuildingsChanged) {
+        self.write(JournalEvent::BuildingsChanged(event.clone()));
+    }
+}
+
+// the calendar hears about a tick before the recorder does, the events know their epoch though
+impl Observer<DayChanged> for Recorder {
+    fn notify(&self, event: &DayChanged) {
+        self.write_at(event.epoch, JournalEvent::DayChanged(*event));
+    }
+}
+
+impl Observer<SeasonChanged> for Recorder {
+    fn notify(&self, event: &SeasonChanged) {
+        self.write_at(event.epoch, JournalEvent::SeasonChanged(*event));
+    }
+}
+
+/// read all entries of a journal
+pub fn read(mut reader: impl Read) -> Result<Vec<Entry>, JournalError> {
+    let mut header = [0; 5];
+    reader.read_exact(&mut header)?;
+    if &header[..4] != MAGIC || header[4] != VERSION {
+        return Err(JournalError::NotAJournal);
+    }
+    let mut entries = vec![];
+    loop {
+        match bincode::deserialize_from(&mut reader) {
+            Ok(entry) => entries.push(entry),
+            Err(error) => match *error {
+                // the end of the journal
+                bincode::ErrorKind::Io(ref io) if io.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok(entries)
+                }
+                _ => return Err(error.into()),
+            },
+        }
+    }
+}
+
+/// Feeds the events of a journal to its observers, without a running simulation.
+pub struct Replayer {
+    entries: Vec<Entry>,
+    position: usize,
+    tickers: Observers<Tick>,
+    tockers: Observers<Tock>,
+    uncovers: Observers<Uncover>,
+    joiners: Observers<TerritoryJoined>,
+    leavers: Observers<TerritoryLeft>,
+    territories_changes: Observers<TerritoriesChanged>,
+    creators: Observers<BuildingCreated>,
+    destroyers: Observers<BuildingDestroyed>,
+    buildings_changes: Observers<BuildingsChanged>,
+    days: Observers<DayChanged>,
+    seasons: Observers<SeasonChanged>,
+}
+
+impl Replayer {
+    pub fn new(entries: Vec<Entry>, dispatch: Dispatch) -> Self {
+        Replayer {
+            entries,
+            position: 0,
+            tickers: Observers::with_dispatch(dispatch),
+            tockers: Observers::with_dispatch(dispatch),
+            uncovers: Observers::with_dispatch(dispatch),
+            joiners: Observers::with_dispatch(dispatch),
+            leavers: Observers::with_dispatch(dispatch),
+            territories_changes: Observers::with_dispatch(dispatch),
+            creators: Observers::with_dispatch(dispatch),
+            destroyers: Observers::with_dispatch(dispatch),
+            buildings_changes: Observers::with_dispatch(dispatch),
+            days: Observers::with_dispatch(dispatch),
+            seasons: Observers::with_dispatch(dispatch),
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>, dispatch: Dispatch) -> Result<Self, JournalError> {
+        let entries = read(BufReader::new(File::open(path)?))?;
+        Ok(Replayer::new(entries, dispatch))
+    }
+
+    /// deliver the replayed events to the observers on `events` as well, e.g. those of the UI
+    pub fn route(&self, events: &EventBus) {
+        events.route(&self.tickers);
+        events.route(&self.tockers);
+        events.route(&self.uncovers);
+        events.route(&self.joiners);
+        events.route(&self.leavers);
+        events.route(&self.territories_changes);
+        events.route(&self.creators);
+        events.route(&self.destroyers);
+        events.route(&self.buildings_changes);
+        events.route(&self.days);
+        events.route(&self.seasons);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.entries.len()
+    }
+
+    /// the epoch of the next entry to replay
+    pub fn epoch(&self) -> Option<usize> {
+        self.entries.get(self.position).map(|entry| entry.epoch)
+    }
+
+    /// start over from the first entry
+    pub fn rewind(&mut self) {
+        self.position = 0;
+    }
+
+    /// replay all entries of the next epoch, returns that epoch
+    pub fn step(&mut self) -> Option<usize> {
+        let epoch = self.epoch()?;
+        self.replay_until(epoch);
+        Some(epoch)
+    }
+
+    /// replay all entries up to and including `epoch`
+    pub fn replay_until(&mut self, epoch: usize) {
+        while let Some(entry) = self.entries.get(self.position) {
+            if entry.epoch > epoch {
+                break;
+            }
+            self.position += 1;
+            self.replay(entry.event.clone());
+        }
+    }
+
+    pub fn replay_all(&mut self) {
+        self.replay_until(usize::MAX);
+    }
+
+    fn replay(&self, event: JournalEvent) {
+        match event {
+            JournalEvent::Tick(event) => self.notify_all(event),
+            JournalEvent::Tock(event) => self.notify_all(event),
+            JournalEvent::Uncover(event) => self.notify_all(event),
+            JournalEvent::TerritoryJoined(event) => self.notify_all(event),
+            JournalEvent::TerritoryLeft(event) => self.notify_all(event),
+            JournalEvent::TerritoriesChanged(event) => self.notify_all(event),
+            JournalEvent::BuildingCreated(event) => self.notify_all(event),
+            JournalEvent::BuildingDestroyed(event) => self.notify_all(event),
+            JournalEvent::BuildingsChanged(event) => self.notify_all(event),
+            JournalEvent::DayChanged(event) => self.notify_all(event),
+            JournalEvent::SeasonChanged(event) => self.notify_all(event),
+        }
+    }
+}
+
+impl Observable<Tick> for Replayer {
+    fn observers(&self) -> &Observers<Tick> {
+        &self.tickers
+    }
+}
+
+impl Observable<Tock> for Replayer {
+    fn observers(&self) -> &Observers<Tock> {
+        &self.tockers
+    }
+}
+
+impl Observable<Uncover> for Replayer {
+    fn observers(&self) -> &Observers<Uncover> {
+        &self.uncovers
+    }
+}
+
+impl Observable<TerritoryJoined> for Replayer {
+    fn observers(&self) -> &Observers<TerritoryJoined> {
+        &self.joiners
+    }
+}
+
+impl Observable<TerritoryLeft> for Replayer {
+    fn observers(&self) -> &Observers<TerritoryLeft> {
+        &self.leavers
+    }
+}
+
+impl Observable<TerritoriesChanged> for Replayer {
+    fn observers(&self) -> &Observers<TerritoriesChanged> {
+        &self.territories_changes
+    }
+}
+
+impl Observable<BuildingCreated> for Replayer {
+    fn observers(&self) -> &Observers<BuildingCreated> {
+        &self.creators
+    }
+}
+
+impl Observable<BuildingDestroyed> for Replayer {
+    fn observers(&self) -> &Observers<BuildingDestroyed> {
+        &self.destroyers
+    }
+}
+
+impl Observable<BuildingsChanged> for Replayer {
+    fn observers(&self) -> &Observers<BuildingsChanged> {
+        &self.buildings_changes
+    }
+}
+
+impl Observable<DayChanged> for Replayer {
+    fn observers(&self) -> &Observers<DayChanged> {
+        &self.days
+    }
+}
+
+impl Observable<SeasonChanged> for Replayer {
+    fn observers(&self) -> &Observers<SeasonChanged> {
+        &self.seasons
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::calendar::Month;
+    use crate::game::Configuration;
+    use crate::map::minimap::{GetByCoordinate, WithGrid};
+    use crate::map::terrain::TerrainType;
+    use crate::observable::Subscription;
+    use crate::tile::TileName;
+    use std::sync::{Arc, RwLock};
+
+    /// a writer the test can still read from after the recorder took it
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<RwLock<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.write().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// collects the events it is notified of
+    struct Events<E>(Mutex<Vec<E>>);
+
+    impl<E> Default for Events<E> {
+        fn default() -> Self {
+            Events(Mutex::new(vec![]))
+        }
+    }
+
+    impl<E: Clone + Send> Observer<E> for Events<E> {
+        fn notify(&self, event: &E) {
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+
+    impl<E: Clone> Events<E> {
+        fn all(&self) -> Vec<E> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    /// what the UI would see of a game or a replay
+    #[derive(Default)]
+    struct Seen {
+        uncovers: Arc<Events<Uncover>>,
+        created: Arc<Events<BuildingCreated>>,
+        days: Arc<Events<DayChanged>>,
+        seasons: Arc<Events<SeasonChanged>>,
+    }
+
+    impl Seen {
+        fn watch(&self, events: &EventBus) -> Vec<Subscription> {
+            vec![
+                events.subscribe(&self.uncovers),
+                events.subscribe(&self.created),
+                events.subscribe(&self.days),
+                events.subscribe(&self.seasons),
+            ]
+        }
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let game = Game::new(Configuration::new(20, 20, 1.).with_dispatch(Dispatch::Synchronous));
+        let live = Seen::default();
+        let _live_subscriptions = live.watch(game.events());
+        let buffer = SharedBuffer::default();
+        let recorder = Recorder::new(buffer.clone()).unwrap().record(&game);
+        game.clock().tick();
+        let warehouse = {
+            let terrain = game.map().terrain();
+            terrain
+                .coordinates()
+                .iter()
+                .find(|coordinate| {
+                    GetByCoordinate::<TerrainType>::get(&*terrain, coordinate)
+                        == TerrainType::Grassland
+                })
+                .unwrap()
+        };
+        game.map()
+            .buildings_controller()
+            .try_construct(warehouse, &TileName::Warehouse)
+            .unwrap();
+        // into spring, the first change of seasons
+        while game.calendar().date(game.clock().epoch()).month() != Month::March {
+            game.clock().tick();
+        }
+        drop(recorder);
+
+        let entries = read(&buffer.0.read().unwrap()[..]).unwrap();
+        let mut replayer = Replayer::new(entries, Dispatch::Synchronous);
+        let replayed = Seen::default();
+        let events = EventBus::new();
+        replayer.route(&events);
+        let _replayed_subscriptions = replayed.watch(&events);
+        assert_eq!(replayer.step(), Some(1));
+        assert_eq!(replayed.days.all().len(), 1);
+        assert!(replayed.uncovers.all() == live.uncovers.all());
+        assert!(replayed.created.all() == live.created.all());
+        replayer.replay_all();
+        assert!(replayer.is_finished());
+        assert!(replayed.days.all() == live.days.all());
+        assert_eq!(replayed.seasons.all().len(), 1);
+        assert!(replayed.seasons.all() == live.seasons.all());
+    }
+
+    #[test]
+    fn test_not_a_journal() {
+        assert!(matches!(
+            read(&b"hello world"[..]),
+            Err(JournalError::NotAJournal)
+        ));
+    }
+}
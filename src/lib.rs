@@ -13,6 +13,7 @@ mod coordinate;
 mod game;
 mod godot;
 mod good;
+mod journal;
 mod map;
 mod observable;
 mod saturating_from;
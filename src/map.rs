@@ -3,17 +3,23 @@ use std::sync::{Arc, RwLock, RwLockReadGuard};
 use buildings::buildings_controller::BuildingsController;
 
 use crate::clock::Clock;
+use crate::game::Configuration;
 use crate::map::buildings::buildings_updater::BuildingsUpdater;
-use crate::map::buildings::Buildings;
-use crate::map::fow::FOW;
+use crate::map::buildings::{BuildingCreated, BuildingDestroyed, Buildings, BuildingsChanged};
+use crate::map::fow::{Uncover, FOW};
 use crate::map::terrain::Terrain;
-use crate::map::territories::Territories;
+use crate::map::territories::{Territories, TerritoriesChanged, TerritoryJoined, TerritoryLeft};
+use crate::observable::event_bus::EventBus;
+use crate::observable::{Observable, Subscribed};
 use std::marker::PhantomData;
 use std::ops::Deref;
 
 pub mod buildings;
+pub mod distance_field;
 pub mod fow;
+pub mod landmasses;
 pub mod minimap;
+pub mod pathfinding;
 pub mod terrain;
 pub mod territories;
 
@@ -24,10 +30,23 @@ pub struct MapStorage {
     pub buildings: Buildings,
 }
 
+impl MapStorage {
+    /// collect territory and building changes until the matching `end_batch`, if batching is enabled
+    pub fn begin_batch(&mut self) {
+        self.territories.begin_batch();
+        self.buildings.begin_batch();
+    }
+
+    pub fn end_batch(&mut self) {
+        self.territories.end_batch();
+        self.buildings.end_batch();
+    }
+}
+
 pub struct Map {
     map_storage: Arc<RwLock<MapStorage>>,
     buildings_controller: BuildingsController,
-    buildings_updater: Arc<BuildingsUpdater>,
+    buildings_updater: Subscribed<BuildingsUpdater>,
 }
 
 pub trait GetRef<T> {
@@ -80,12 +99,35 @@ impl<'reference, E, T: GetRef<E>> From<RwLockReadGuard<'reference, T>>
 }
 
 impl Map {
-    pub fn new(clock: &Clock, rows: usize, columns: usize, island_noise: f64) -> Self {
+    pub fn new(clock: &Clock, configuration: &Configuration) -> Self {
+        let rows = configuration.rows();
+        let columns = configuration.columns();
+        let dispatch = configuration.dispatch();
+        let mut territories = Territories::new(rows, columns, dispatch);
+        territories.set_batching(configuration.batched_events());
+        let mut buildings = Buildings::new(rows, columns, dispatch);
+        buildings.set_batching(configuration.batched_events());
         let map_storage = Arc::new(RwLock::new(MapStorage {
-            terrain: Terrain::new(rows, columns, island_noise),
-            territories: Default::default(),
-            fow: Default::default(),
-            buildings: Default::default(),
+            terrain: if configuration.wrapped_longitude() {
+                Terrain::new_wrapped_seeded(
+                    configuration.seed(),
+                    rows,
+                    columns,
+                    configuration.island_noise(),
+                    configuration.terrain_rules(),
+                )
+            } else {
+                Terrain::new_seeded(
+                    configuration.seed(),
+                    rows,
+                    columns,
+                    configuration.island_noise(),
+                    configuration.terrain_rules(),
+                )
+            },
+            territories,
+            fow: FOW::new(rows, columns, dispatch),
+            buildings,
         }));
 
         Map {
@@ -118,4 +160,18 @@ impl Map {
     pub fn buildings_controller(&self) -> &BuildingsController {
         &self.buildings_controller
     }
+
+    /// route the events of all layers to `events`
+    pub fn route_events(&self, events: &EventBus) {
+        let map = self.map_storage();
+        events.route(Observable::<Uncover>::observers(&map.fow));
+        events.route(Observable::<TerritoryJoined>::observers(&map.territories));
+        events.route(Observable::<TerritoryLeft>::observers(&map.territories));
+        events.route(Observable::<TerritoriesChanged>::observers(
+            &map.territories,
+        ));
+        events.route(Observable::<BuildingCreated>::observers(&map.buildings));
+        events.route(Observable::<BuildingDestroyed>::observers(&map.buildings));
+        events.route(Observable::<BuildingsChanged>::observers(&map.buildings));
+    }
 }
@@ -5,11 +5,14 @@ use rayon::iter::{ParallelBridge, ParallelIterator};
 
 use crate::coordinate::indexed::CoordinateIndexed;
 use crate::coordinate::Coordinate;
+use crate::map::buildings::buildings_index::BuildingsIndex;
 use crate::map::minimap::{GetRefByCoordinate, SetByCoordinate, TrySetByCoordinate, WithGrid};
-use crate::observable::{Observable, Observers};
+use crate::observable::{Batch, Dispatch, Observable, Observers};
 use crate::tile::{TileInstance, TileName};
+use serde::{Deserialize, Serialize};
 
 pub mod buildings_controller;
+pub mod buildings_index;
 pub mod buildings_updater;
 pub mod territories_state;
 
@@ -18,25 +21,71 @@ pub type SynchronizedInstance = RwLock<TileInstance>;
 #[derive(Default)]
 pub struct Buildings {
     buildings: CoordinateIndexed<SynchronizedInstance>,
+    index: BuildingsIndex,
     rows: usize,
     columns: usize,
     creators: Observers<BuildingCreated>,
     destroyers: Observers<BuildingDestroyed>,
+    batch: Batch<BuildingsChanged>,
+    changes: Observers<BuildingsChanged>,
 }
 
 impl Buildings {
-    pub fn new(rows: usize, columns: usize) -> Self {
+    pub fn new(rows: usize, columns: usize, dispatch: Dispatch) -> Self {
         Buildings {
             buildings: Default::default(),
+            index: Default::default(),
             rows,
             columns,
-            creators: Default::default(),
-            destroyers: Default::default(),
+            creators: Observers::with_dispatch(dispatch),
+            destroyers: Observers::with_dispatch(dispatch),
+            batch: Default::default(),
+            changes: Observers::with_dispatch(dispatch),
         }
     }
 
-    fn get_lock(&self, coordinate: &Coordinate) -> Option<&SynchronizedInstance> {
-        self.buildings.get(coordinate)
+    /// if enabled, changes are only delivered as `BuildingsChanged`, one per batch
+    pub fn set_batching(&mut self, enabled: bool) {
+        self.batch.set_enabled(enabled);
+    }
+
+    pub fn begin_batch(&mut self) {
+        self.batch.begin();
+    }
+
+    pub fn end_batch(&mut self) {
+        if self.batch.end() {
+            self.flush_batch();
+        }
+    }
+
+    fn flush_batch(&mut self) {
+        let changes = self.batch.take();
+        if !changes.is_empty() {
+            self.notify_all(changes);
+        }
+    }
+
+    fn created(&mut self, created: BuildingCreated) {
+        if self.batch.is_enabled() {
+            self.batch.pending_mut().created.push(created);
+            if !self.batch.is_open() {
+                self.flush_batch();
+            }
+        } else {
+            self.notify_all(created);
+        }
+    }
+
+    fn destroyed(&mut self, destroyed: BuildingDestroyed) {
+        if self.batch.is_enabled() {
+            self.batch.pending_mut().destroyed.push(destroyed);
+            if !self.batch.is_open() {
+                self.flush_batch();
+            }
+        } else {
+            self.notify_all(destroyed);
+        }
     }
 
     fn get_mut(&self, coordinate: &Coordinate) -> Option<RwLockWriteGuard<TileInstance>> {
@@ -62,6 +111,11 @@ impl Buildings {
         try_mut_instance.unwrap()
     }
 
+    /// where the buildings are, always in sync with the created and destroyed events
+    pub fn index(&self) -> &BuildingsIndex {
+        &self.index
+    }
+
     pub fn par_coordinates(&self) -> impl ParallelIterator<Item = &Coordinate> {
         self.buildings.keys().par_bridge()
     }
@@ -96,14 +150,18 @@ impl SetByCoordinate<Option<TileInstance>> for Buildings {
             Some(instance) => {
                 let tile_name: TileName = instance.tile().into();
                 self.buildings.insert(coordinate, RwLock::new(instance));
-                self.notify_all(BuildingCreated {
+                let created = BuildingCreated {
                     coordinate,
                     tile_name,
-                });
+                };
+                self.index.created(&created);
+                self.created(created);
             }
             None => {
                 self.buildings.remove(&coordinate);
-                self.notify_all(BuildingDestroyed { coordinate });
+                let destroyed = BuildingDestroyed { coordinate };
+                self.index.destroyed(&destroyed);
+                self.destroyed(destroyed);
             }
         };
     }
@@ -119,7 +177,7 @@ impl TrySetByCoordinate<Option<TileInstance>> for Buildings {
     }
 }
 
-#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BuildingCreated {
     pub coordinate: Coordinate,
     pub tile_name: TileName,
@@ -131,7 +189,7 @@ impl Observable<BuildingCreated> for Buildings {
     }
 }
 
-#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BuildingDestroyed {
     pub coordinate: Coordinate,
 }
@@ -141,3 +199,30 @@ impl Observable<BuildingDestroyed> for Buildings {
         &self.destroyers
     }
 }
+
+/// all building changes of one batch
+#[derive(Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildingsChanged {
+    pub created: Vec<BuildingCreated>,
+    pub destroyed: Vec<BuildingDestroyed>,
+}
+
+impl BuildingsChanged {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.destroyed.is_empty()
+    }
+
+    pub fn coordinates(&self) -> Vec<Coordinate> {
+        self.created
+            .iter()
+            .map(|created| created.coordinate)
+            .chain(self.destroyed.iter().map(|destroyed| destroyed.coordinate))
+            .collect()
+    }
+}
+
+impl Observable<BuildingsChanged> for Buildings {
+    fn observers(&self) -> &Observers<BuildingsChanged> {
+        &self.changes
+    }
+}
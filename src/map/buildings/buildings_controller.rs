@@ -1,7 +1,7 @@
 use crate::coordinate::Coordinate;
 use crate::good::Good;
 use crate::map::minimap::GetRefByCoordinate;
-use crate::map::minimap::{FillByCoordinate, GetByCoordinate, SetByCoordinate};
+use crate::map::minimap::{FillByCoordinate, GetByCoordinate, SetByCoordinate, WithGrid};
 use crate::map::territories::{TerritoriesState, TerritoriesStateRw, TerritoryID};
 use crate::map::MapStorage;
 use crate::tile::{Tile, TileInstance, TileName};
@@ -16,6 +16,7 @@ pub enum ConstructionError {
     InvalidTerritory,
     InsufficientResources,
     CoordinateOccupied,
+    OutOfBounds,
 }
 
 impl fmt::Display for ConstructionError {
@@ -42,6 +43,12 @@ impl BuildingsController {
     ) -> Result<(), ConstructionError> {
         let map = self.map_storage.write().unwrap();
 
+        // are we even on the map?
+        if !map.terrain.contains(&coordinate) {
+            return Err(ConstructionError::OutOfBounds);
+        }
+        let coordinate = map.terrain.normalize(&coordinate);
+
         // are we in a valid territory?
         let maybe_territory_id: Option<TerritoryID> = map.territories.get(&coordinate);
         let is_warehouse = tile_name == &TileName::Warehouse;
@@ -83,12 +90,16 @@ impl BuildingsController {
         coordinate: Coordinate,
         tile: &'static dyn Tile,
     ) {
+        // the whole construction is delivered as a single batch
+        map.begin_batch();
+
         // create the building
         map.buildings
             .set(coordinate, Some(TileInstance::from(tile)));
 
         // update the fog of war
-        let influence = tile.influence_at(&coordinate);
+        // the influence stops at the edge of the map
+        let influence = map.terrain.clip(&tile.influence_at(&coordinate));
         map.fow.fill(influence.clone(), true);
 
         // extend the territory
@@ -108,5 +119,6 @@ impl BuildingsController {
                 state += add;
             }
         }
+        map.end_batch();
     }
 }
@@ -0,0 +1,126 @@
+use crate::coordinate::range::Range;
+use crate::coordinate::Coordinate;
+use crate::map::buildings::{BuildingCreated, BuildingDestroyed};
+use crate::tile::TileName;
+use std::collections::HashMap;
+
+/// Where the buildings are, by kind. Queries intersect the chunks of the asked range with the
+/// occupied ones, so they don't look at every empty coordinate.
+#[derive(Default)]
+pub struct BuildingsIndex {
+    all: Range,
+    by_tile_name: HashMap<TileName, Range>,
+    tile_names: HashMap<Coordinate, TileName>,
+}
+
+impl BuildingsIndex {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn created(&mut self, created: &BuildingCreated) {
+        // a replaced building is no longer of its old kind
+        self.destroyed(&BuildingDestroyed {
+            coordinate: created.coordinate,
+        });
+        self.all.insert(created.coordinate);
+        self.by_tile_name
+            .entry(created.tile_name)
+            .or_default()
+            .insert(created.coordinate);
+        self.tile_names
+            .insert(created.coordinate, created.tile_name);
+    }
+
+    pub fn destroyed(&mut self, destroyed: &BuildingDestroyed) {
+        if let Some(tile_name) = self.tile_names.remove(&destroyed.coordinate) {
+            self.all.remove(&destroyed.coordinate);
+            if let Some(range) = self.by_tile_name.get_mut(&tile_name) {
+                range.remove(&destroyed.coordinate);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tile_names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tile_names.is_empty()
+    }
+
+    pub fn tile_name(&self, coordinate: &Coordinate) -> Option<TileName> {
+        self.tile_names.get(coordinate).copied()
+    }
+
+    fn occupied(&self, tile_name: Option<&TileName>) -> Option<&Range> {
+        match tile_name {
+            Some(tile_name) => self.by_tile_name.get(tile_name),
+            None => Some(&self.all),
+        }
+    }
+
+    /// the buildings in `range`, only those of `tile_name` if given
+    pub fn in_range(&self, range: &Range, tile_name: Option<&TileName>) -> Range {
+        match self.occupied(tile_name) {
+            Some(occupied) => range & occupied,
+            None => Range::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinate::range::{RangeFactory, RangeFrom};
+
+    #[test]
+    fn test_buildings_index() {
+        let mut index = BuildingsIndex::new();
+        let warehouse = Coordinate::new(0, 0);
+        let pioneer = Coordinate::new(2, 0);
+        let far_away = Coordinate::new(40, -20);
+        index.created(&BuildingCreated {
+            coordinate: warehouse,
+            tile_name: TileName::Warehouse,
+        });
+        for coordinate in [pioneer, far_away].iter() {
+            index.created(&BuildingCreated {
+                coordinate: *coordinate,
+                tile_name: TileName::Pioneer,
+            });
+        }
+        assert_eq!(index.len(), 3);
+        assert_eq!(
+            index.in_range(&warehouse.circle(2), None),
+            Range::new(&[warehouse, pioneer])
+        );
+        assert_eq!(
+            index.in_range(&warehouse.circle(2), Some(&TileName::Pioneer)),
+            Range::new(&[pioneer])
+        );
+        assert!(index
+            .in_range(&warehouse.circle(1), Some(&TileName::Pioneer))
+            .is_empty());
+        assert_eq!(
+            index.in_range(&warehouse.circle(0), None),
+            Range::new(&[warehouse])
+        );
+
+        index.destroyed(&BuildingDestroyed {
+            coordinate: pioneer,
+        });
+        index.created(&BuildingCreated {
+            coordinate: warehouse,
+            tile_name: TileName::Pioneer,
+        });
+        assert_eq!(index.len(), 2);
+        assert!(index
+            .in_range(&warehouse.circle(2), Some(&TileName::Warehouse))
+            .is_empty());
+        assert_eq!(
+            index.in_range(&warehouse.circle(2), Some(&TileName::Pioneer)),
+            Range::new(&[warehouse])
+        );
+    }
+}
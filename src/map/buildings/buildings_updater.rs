@@ -1,6 +1,7 @@
-use crate::clock::{Clock, Tick, Tock};
+use crate::clock::{Clock, Phase, Tick};
+use crate::map::minimap::WithGrid;
 use crate::map::MapStorage;
-use crate::observable::Observer;
+use crate::observable::{Observer, Subscribed};
 use rayon::prelude::*;
 use std::sync::{Arc, RwLock};
 
@@ -9,21 +10,51 @@ pub struct BuildingsUpdater {
 }
 
 impl Observer<Tick> for BuildingsUpdater {
-    fn notify(&self, _event: &Tick) {
+    fn notify(&self, tick: &Tick) {
+        match tick.phase() {
+            Phase::Consume => self.consume(),
+            Phase::Produce => self.produce(),
+            _ => {}
+        }
+    }
+}
+
+impl BuildingsUpdater {
+    pub fn new(clock: &Clock, map_storage: Arc<RwLock<MapStorage>>) -> Subscribed<Self> {
+        Subscribed::new(BuildingsUpdater { map_storage })
+            .subscribe(clock.phase(Phase::Consume))
+            .subscribe(clock.phase(Phase::Produce))
+    }
+
+    fn consume(&self) {
         let map = self.map_storage.read().unwrap();
         map.buildings.par_coordinates().for_each(|coordinate| {
-            let mut mut_instance = map.buildings.spin_get_mut(coordinate);
-            let influence = mut_instance.tile().influence_at(coordinate);
-            for other_coordinate in influence {
-                let mut other_mut_instance = map.buildings.spin_get_mut(&other_coordinate);
-                mut_instance.consume(&mut *other_mut_instance);
+            let tile = map.buildings.spin_get_mut(coordinate).tile();
+            // wrapped around the map, the influence reaches across the seam
+            let influence = map.terrain.clip(&tile.influence_at(coordinate));
+            // only look at the coordinates that actually have a building
+            for other_coordinate in map.buildings.index().in_range(&influence, None) {
+                if &other_coordinate == coordinate {
+                    continue;
+                }
+                // always lock in coordinate order so two buildings in each others influence can't deadlock
+                let (mut first, mut second) = if coordinate < &other_coordinate {
+                    let first = map.buildings.spin_get_mut(coordinate);
+                    (first, map.buildings.spin_get_mut(&other_coordinate))
+                } else {
+                    let first = map.buildings.spin_get_mut(&other_coordinate);
+                    (first, map.buildings.spin_get_mut(coordinate))
+                };
+                if coordinate < &other_coordinate {
+                    first.consume(&mut second);
+                } else {
+                    second.consume(&mut first);
+                }
             }
         });
     }
-}
 
-impl Observer<Tock> for BuildingsUpdater {
-    fn notify(&self, _event: &Tock) {
+    fn produce(&self) {
         let map = self.map_storage.read().unwrap();
         map.buildings.par_coordinates().for_each(|coordinate| {
             let mut mut_instance = map.buildings.spin_get_mut(coordinate);
@@ -31,12 +62,3 @@ impl Observer<Tock> for BuildingsUpdater {
         });
     }
 }
-
-impl BuildingsUpdater {
-    pub fn new(clock: &Clock, map_storage: Arc<RwLock<MapStorage>>) -> Arc<Self> {
-        let observer = Arc::new(BuildingsUpdater { map_storage });
-        clock.tickers().register(&observer);
-        clock.tockers().register(&observer);
-        observer
-    }
-}
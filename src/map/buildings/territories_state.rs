@@ -259,17 +259,21 @@ mod tests {
     use crate::coordinate::Coordinate;
     use crate::good::{Good, ImmaterialGood};
     use crate::map::buildings::buildings_controller::BuildingsController;
+    use crate::map::buildings::Buildings;
+    use crate::map::fow::FOW;
     use crate::map::minimap::GetByCoordinate;
     use crate::map::terrain::Terrain;
+    use crate::map::territories::Territories;
+    use crate::observable::Dispatch;
     use std::sync::{Arc, RwLock};
 
     #[test]
     fn test_simple_update() {
         let map_storage = Arc::new(RwLock::new(MapStorage {
-            terrain: Terrain::new_seeded(3, 20, 20, 0.),
-            territories: Default::default(),
-            fow: Default::default(),
-            buildings: Default::default(),
+            terrain: Terrain::new_seeded(3, 20, 20, 0., Default::default()),
+            territories: Territories::new(20, 20, Dispatch::default()),
+            fow: FOW::new(20, 20, Dispatch::default()),
+            buildings: Buildings::new(20, 20, Dispatch::default()),
         }));
         BuildingsController::do_construct(
             map_storage.write().unwrap(),
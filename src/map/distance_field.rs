@@ -0,0 +1,162 @@
+use crate::coordinate::range::{Range, RangeFactory};
+use crate::coordinate::Coordinate;
+use crate::map::minimap::{GetByCoordinate, WithGrid};
+use crate::map::pathfinding::MovementCosts;
+use crate::map::terrain::TerrainType;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// The distance from the nearest of a set of seeds to every coordinate on the grid that can be
+/// reached from them, also known as a Dijkstra map.
+#[derive(Default, Clone)]
+pub struct DistanceField {
+    distances: HashMap<Coordinate, u32>,
+}
+
+impl DistanceField {
+    /// in steps from one neighbor to the next, regardless of the terrain
+    pub fn hops<M: WithGrid>(map: &M, seeds: &Range) -> Self {
+        Self::compute(map, seeds, |_| Some(1))
+    }
+
+    /// the summed cost of entering every coordinate along the cheapest way, the seeds are free
+    pub fn costs<M>(map: &M, costs: &MovementCosts, seeds: &Range) -> Self
+    where
+        M: GetByCoordinate<TerrainType> + WithGrid,
+    {
+        Self::compute(map, seeds, |coordinate| costs.cost(&map.get(coordinate)))
+    }
+
+    fn compute<M: WithGrid>(
+        map: &M,
+        seeds: &Range,
+        mut entry_cost: impl FnMut(&Coordinate) -> Option<u32>,
+    ) -> Self {
+        let mut distances = HashMap::new();
+        let mut open = BinaryHeap::new();
+        for seed in seeds.iter().filter(|seed| map.contains(seed)) {
+            let seed = map.normalize(&seed);
+            distances.insert(seed, 0);
+            open.push(Reverse((0, seed)));
+        }
+        while let Some(Reverse((distance, current))) = open.pop() {
+            if distances[&current] < distance {
+                // already reached cheaper from another seed
+                continue;
+            }
+            for neighbor in Range::neighbors(&current).iter() {
+                if !map.contains(&neighbor) {
+                    continue;
+                }
+                let neighbor = map.normalize(&neighbor);
+                let neighbor_distance = match entry_cost(&neighbor) {
+                    Some(cost) => distance + cost,
+                    None => continue,
+                };
+                if distances
+                    .get(&neighbor)
+                    .map_or(false, |known| *known <= neighbor_distance)
+                {
+                    continue;
+                }
+                distances.insert(neighbor, neighbor_distance);
+                open.push(Reverse((neighbor_distance, neighbor)));
+            }
+        }
+        DistanceField { distances }
+    }
+
+    /// `None` if `coordinate` can't be reached from any seed
+    pub fn get(&self, coordinate: &Coordinate) -> Option<u32> {
+        self.distances.get(coordinate).copied()
+    }
+
+    /// how many coordinates were reached
+    pub fn len(&self) -> usize {
+        self.distances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.distances.is_empty()
+    }
+
+    /// every reached coordinate at most `distance` away from the nearest seed
+    pub fn within(&self, distance: u32) -> Range {
+        self.distances
+            .iter()
+            .filter(|(_, known)| **known <= distance)
+            .map(|(coordinate, _)| *coordinate)
+            .collect()
+    }
+
+    /// the neighbor that is closest to a seed, `None` at a seed or where nothing was reached
+    pub fn step_towards_seed(&self, coordinate: &Coordinate) -> Option<Coordinate> {
+        let distance = self.get(coordinate)?;
+        let mut neighbors: Vec<Coordinate> = Range::neighbors(coordinate).into_iter().collect();
+        // the range is unordered, sort for reproducible steps
+        neighbors.sort();
+        neighbors
+            .into_iter()
+            .filter_map(|neighbor| Some((self.get(&neighbor)?, neighbor)))
+            .filter(|(neighbor_distance, _)| *neighbor_distance < distance)
+            .min()
+            .map(|(_, neighbor)| neighbor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinate::dist::Dist;
+
+    /// grassland with a few exceptions
+    struct TestMap(HashMap<Coordinate, TerrainType>);
+
+    impl GetByCoordinate<TerrainType> for TestMap {
+        fn get(&self, coordinate: &Coordinate) -> TerrainType {
+            *self.0.get(coordinate).unwrap_or(&TerrainType::Grassland)
+        }
+    }
+
+    impl WithGrid for TestMap {
+        fn rows(&self) -> usize {
+            10
+        }
+
+        fn columns(&self) -> usize {
+            10
+        }
+    }
+
+    #[test]
+    fn test_distance_field() {
+        let mut terrain = HashMap::new();
+        terrain.insert(Coordinate::new(1, 0), TerrainType::Hills);
+        terrain.insert(Coordinate::new(-1, 0), TerrainType::Mountain);
+        let map = TestMap(terrain);
+        let seeds = Range::new(&[Coordinate::new(0, 0), Coordinate::new(4, -4)]);
+
+        let hops = DistanceField::hops(&map, &seeds);
+        assert_eq!(hops.len(), 100);
+        for coordinate in map.coordinates().iter() {
+            let nearest = seeds.iter().map(|seed| seed.dist(&coordinate)).min();
+            assert_eq!(hops.get(&coordinate), nearest);
+        }
+        assert_eq!(hops.within(0), seeds);
+
+        let costs = DistanceField::costs(&map, &MovementCosts::land(), &seeds);
+        assert_eq!(costs.len(), 99);
+        assert_eq!(costs.get(&Coordinate::new(-1, 0)), None);
+        assert_eq!(costs.get(&Coordinate::new(1, 0)), Some(3));
+        // around the hills is cheaper
+        assert_eq!(costs.get(&Coordinate::new(2, 0)), Some(3));
+
+        let start = Coordinate::new(-3, 2);
+        let mut path = vec![start];
+        while let Some(next) = costs.step_towards_seed(path.last().unwrap()) {
+            path.push(next);
+        }
+        assert_eq!(path.last(), Some(&Coordinate::new(0, 0)));
+        assert_eq!(path.len() as u32, costs.get(&start).unwrap() + 1);
+    }
+}
@@ -1,8 +1,9 @@
 use crate::coordinate::range::Range;
 use crate::coordinate::Coordinate;
 use crate::map::minimap::{FillByCoordinate, GetByCoordinate, Minimap, SetByCoordinate, WithGrid};
-use crate::observable::{Observable, Observers};
+use crate::observable::{Dispatch, Observable, Observers};
 use derive_more::{Constructor, From, Into};
+use serde::{Deserialize, Serialize};
 
 #[derive(Default)]
 pub struct FOW {
@@ -13,16 +14,19 @@ pub struct FOW {
 }
 
 impl FOW {
-    pub fn new(rows: usize, columns: usize) -> Self {
+    pub fn new(rows: usize, columns: usize, dispatch: Dispatch) -> Self {
         FOW {
             fow: Default::default(),
             rows,
             columns,
-            observers: Observers::new(),
+            observers: Observers::with_dispatch(dispatch),
         }
     }
 
     fn set_silent(&mut self, coordinate: Coordinate, value: bool) {
+        if !self.contains(&coordinate) {
+            return;
+        }
         if value {
             self.fow.insert(coordinate);
         } else {
@@ -47,8 +51,12 @@ impl GetByCoordinate<bool> for FOW {
     }
 }
 
+/// off the grid there is nothing to uncover
 impl SetByCoordinate<bool> for FOW {
     fn set(&mut self, coordinate: Coordinate, value: bool) {
+        if !self.contains(&coordinate) {
+            return;
+        }
         self.set_silent(coordinate, value);
         let uncover = Uncover::new(vec![coordinate]);
         self.notify_all(uncover)
@@ -57,7 +65,8 @@ impl SetByCoordinate<bool> for FOW {
 
 impl FillByCoordinate<bool> for FOW {
     fn fill(&mut self, range: Range, value: bool) {
-        let uncover = Uncover::new(range.iter().copied().collect());
+        let range = self.clip(&range);
+        let uncover = Uncover::new(range.iter().collect());
         range.into_iter().for_each(|c| {
             self.set_silent(c, value);
         });
@@ -67,7 +76,7 @@ impl FillByCoordinate<bool> for FOW {
 
 impl Minimap<bool> for FOW {}
 
-#[derive(Default, Clone, PartialEq, Eq, From, Into, Constructor)]
+#[derive(Default, Clone, PartialEq, Eq, From, Into, Constructor, Serialize, Deserialize)]
 pub struct Uncover(Vec<Coordinate>);
 
 impl Uncover {
@@ -81,3 +90,39 @@ impl Observable<Uncover> for FOW {
         &self.observers
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinate::range::RangeFrom;
+    use crate::coordinate::Offset;
+    use crate::observable::Observer;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct UncoverRecorder(Mutex<Vec<Uncover>>);
+
+    impl Observer<Uncover> for UncoverRecorder {
+        fn notify(&self, event: &Uncover) {
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut fow = FOW::new(4, 4, Dispatch::Synchronous);
+        let recorder = Arc::new(UncoverRecorder::default());
+        let _subscription = fow.observers().register(&recorder);
+        let corner = Coordinate::from(Offset::new(1, 1));
+        let outside = Coordinate::from(Offset::new(2, 1));
+        fow.fill(corner.circle(1), true);
+        assert!(fow.get(&corner));
+        assert!(!fow.get(&outside));
+        let uncovered = recorder.0.lock().unwrap()[0].coordinates().clone();
+        assert!(uncovered.iter().all(|c| fow.contains(c)));
+
+        fow.set(outside, true);
+        assert!(!fow.get(&outside));
+        assert_eq!(recorder.0.lock().unwrap().len(), 1);
+    }
+}
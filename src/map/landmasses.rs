@@ -0,0 +1,258 @@
+use crate::coordinate::range::{Range, RangeFactory};
+use crate::coordinate::{Coordinate, Offset};
+use crate::map::minimap::{GetByCoordinate, WithGrid};
+use crate::map::terrain::TerrainType;
+use derive_more::{Constructor, From, Into};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+/// Numbered in the order they are found, row by row, so the same terrain gets the same ids.
+#[derive(Debug, Default, Hash, Clone, Copy, PartialEq, Eq, Constructor, From, Into)]
+pub struct LandmassID(usize);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LandmassKind {
+    Island,
+    /// fresh water, or salt water that doesn't reach the edge of the map
+    Lake,
+    Ocean,
+}
+
+/// which coordinates are connected, fresh and salt water are kept apart
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Class {
+    Land,
+    FreshWater,
+    SaltWater,
+}
+
+impl From<TerrainType> for Class {
+    fn from(terrain_type: TerrainType) -> Self {
+        match terrain_type {
+            TerrainType::FreshWater => Class::FreshWater,
+            _ if terrain_type.is_water() => Class::SaltWater,
+            _ => Class::Land,
+        }
+    }
+}
+
+/// a contiguous area of land or water
+pub struct Landmass {
+    id: LandmassID,
+    kind: LandmassKind,
+    range: Range,
+    coastline: Range,
+    bounds: (Offset, Offset),
+}
+
+impl Landmass {
+    pub fn id(&self) -> LandmassID {
+        self.id
+    }
+
+    pub fn kind(&self) -> LandmassKind {
+        self.kind
+    }
+
+    pub fn range(&self) -> &Range {
+        &self.range
+    }
+
+    pub fn size(&self) -> usize {
+        self.range.len()
+    }
+
+    /// the coordinates next to water for land and next to land for water
+    pub fn coastline(&self) -> &Range {
+        &self.coastline
+    }
+
+    /// the first and last column and row, on a wrapped map it may span the whole width
+    pub fn bounds(&self) -> (Offset, Offset) {
+        self.bounds
+    }
+}
+
+pub struct Landmasses {
+    by_coordinate: HashMap<Coordinate, LandmassID>,
+    landmasses: Vec<Landmass>,
+}
+
+impl Landmasses {
+    /// label every coordinate on the grid of `map`
+    pub fn detect<M>(map: &M) -> Self
+    where
+        M: GetByCoordinate<TerrainType> + WithGrid,
+    {
+        let mut coordinates: Vec<Coordinate> = map.coordinates().iter().collect();
+        coordinates.sort_by_key(|coordinate| {
+            let offset: Offset = coordinate.into();
+            (offset.row(), offset.column())
+        });
+        let mut classes: HashMap<Coordinate, Class> = HashMap::new();
+        let mut class_of = |coordinate: &Coordinate| -> Class {
+            *classes
+                .entry(*coordinate)
+                .or_insert_with(|| map.get(coordinate).into())
+        };
+
+        let mut landmasses = Landmasses {
+            by_coordinate: HashMap::new(),
+            landmasses: vec![],
+        };
+        for start in coordinates {
+            if landmasses.by_coordinate.contains_key(&start) {
+                continue;
+            }
+            let id = LandmassID::new(landmasses.landmasses.len());
+            let class = class_of(&start);
+            let mut range = Range::default();
+            let mut coastline = Range::default();
+            let mut reaches_edge = false;
+            let mut queue = VecDeque::new();
+            landmasses.by_coordinate.insert(start, id);
+            queue.push_back(start);
+            while let Some(coordinate) = queue.pop_front() {
+                range.insert(coordinate);
+                for neighbor in Range::neighbors(&coordinate).iter() {
+                    if !map.contains(&neighbor) {
+                        reaches_edge = true;
+                        continue;
+                    }
+                    let neighbor = map.normalize(&neighbor);
+                    let neighbor_class = class_of(&neighbor);
+                    if neighbor_class == class {
+                        if let Entry::Vacant(entry) = landmasses.by_coordinate.entry(neighbor) {
+                            entry.insert(id);
+                            queue.push_back(neighbor);
+                        }
+                    } else if (neighbor_class == Class::Land) != (class == Class::Land) {
+                        coastline.insert(coordinate);
+                    }
+                }
+            }
+            let kind = match class {
+                Class::Land => LandmassKind::Island,
+                Class::SaltWater if reaches_edge => LandmassKind::Ocean,
+                _ => LandmassKind::Lake,
+            };
+            let bounds = bounds(&range);
+            landmasses.landmasses.push(Landmass {
+                id,
+                kind,
+                range,
+                coastline,
+                bounds,
+            });
+        }
+        landmasses
+    }
+
+    pub fn get(&self, id: &LandmassID) -> Option<&Landmass> {
+        self.landmasses.get(id.0)
+    }
+
+    /// the landmass `coordinate` belongs to, `None` if it is off the grid
+    pub fn at(&self, coordinate: &Coordinate) -> Option<&Landmass> {
+        self.by_coordinate
+            .get(coordinate)
+            .and_then(|id| self.get(id))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Landmass> {
+        self.landmasses.iter()
+    }
+
+    pub fn of_kind(&self, kind: LandmassKind) -> impl Iterator<Item = &Landmass> {
+        self.iter().filter(move |landmass| landmass.kind == kind)
+    }
+
+    pub fn islands(&self) -> impl Iterator<Item = &Landmass> {
+        self.of_kind(LandmassKind::Island)
+    }
+
+    pub fn len(&self) -> usize {
+        self.landmasses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.landmasses.is_empty()
+    }
+}
+
+fn bounds(range: &Range) -> (Offset, Offset) {
+    let offsets: Vec<Offset> = range
+        .iter()
+        .map(|coordinate| (&coordinate).into())
+        .collect();
+    let columns = offsets.iter().map(Offset::column);
+    let rows = offsets.iter().map(Offset::row);
+    (
+        Offset::new(columns.clone().min().unwrap(), rows.clone().min().unwrap()),
+        Offset::new(columns.max().unwrap(), rows.max().unwrap()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ocean with a few exceptions
+    struct TestMap(HashMap<Coordinate, TerrainType>);
+
+    impl GetByCoordinate<TerrainType> for TestMap {
+        fn get(&self, coordinate: &Coordinate) -> TerrainType {
+            *self.0.get(coordinate).unwrap_or(&TerrainType::Ocean)
+        }
+    }
+
+    impl WithGrid for TestMap {
+        fn rows(&self) -> usize {
+            10
+        }
+
+        fn columns(&self) -> usize {
+            10
+        }
+    }
+
+    #[test]
+    fn test_landmasses() {
+        let mut terrain = HashMap::new();
+        // an island with an enclosed bay and a pond
+        let center = Coordinate::new(-2, 1);
+        for coordinate in Range::ring(&center, 1).iter() {
+            terrain.insert(coordinate, TerrainType::Grassland);
+        }
+        terrain.insert(center + Coordinate::new(-1, 0), TerrainType::FreshWater);
+        // and a single rock
+        let rock = Coordinate::new(3, 0);
+        terrain.insert(rock, TerrainType::Mountain);
+        let map = TestMap(terrain);
+
+        let landmasses = Landmasses::detect(&map);
+        assert_eq!(landmasses.islands().count(), 2);
+        let island = landmasses.at(&(center + Coordinate::new(1, 0))).unwrap();
+        assert_eq!(island.kind(), LandmassKind::Island);
+        assert_eq!(island.size(), 5);
+        assert_eq!(island.coastline(), island.range());
+        assert_eq!(landmasses.at(&rock).unwrap().size(), 1);
+
+        let bay = landmasses.at(&center).unwrap();
+        assert_eq!(bay.kind(), LandmassKind::Lake);
+        assert_eq!(bay.size(), 1);
+        let pond = landmasses.at(&(center + Coordinate::new(-1, 0))).unwrap();
+        assert_eq!(pond.kind(), LandmassKind::Lake);
+        let ocean = landmasses.at(&Coordinate::new(0, 0)).unwrap();
+        assert_eq!(ocean.kind(), LandmassKind::Ocean);
+        assert_eq!(ocean.size(), 100 - 5 - 1 - 1 - 1);
+        assert_eq!(landmasses.iter().map(Landmass::size).sum::<usize>(), 100);
+
+        // found in the same order every time
+        let again = Landmasses::detect(&map);
+        assert_eq!(
+            again.at(&rock).unwrap().id(),
+            landmasses.at(&rock).unwrap().id()
+        );
+    }
+}
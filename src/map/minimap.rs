@@ -1,4 +1,6 @@
-use crate::coordinate::range::Range;
+use crate::coordinate::dist::Dist;
+use crate::coordinate::range::{Range, RangeFactory};
+use crate::coordinate::wrap::Wrap;
 use crate::coordinate::{Coordinate, Offset};
 
 pub trait GetByCoordinate<T> {
@@ -6,7 +8,7 @@ pub trait GetByCoordinate<T> {
     fn get_range(&self, range: &Range) -> Vec<T> {
         range
             .into_iter()
-            .map(|coordinate| self.get(coordinate))
+            .map(|coordinate| self.get(&coordinate))
             .collect()
     }
 }
@@ -16,7 +18,7 @@ pub trait GetRefByCoordinate<'a, T> {
     fn get_range(&'a self, range: &Range) -> Vec<T> {
         range
             .into_iter()
-            .map(|coordinate| self.get(coordinate))
+            .map(|coordinate| self.get(&coordinate))
             .collect()
     }
 }
@@ -50,13 +52,88 @@ pub trait FillClonedByCoordinate<T: Clone>: SetByCoordinate<T> {
     }
 }
 
+/// the first and last row or column of a grid `length` long, centered on 0
+fn bounds(length: usize) -> (i32, i32) {
+    let length = length as i32;
+    (-(length / 2), length - length / 2 - 1)
+}
+
 pub trait WithGrid {
     fn rows(&self) -> usize;
     fn columns(&self) -> usize;
+
+    /// `Some` if the grid wraps along the longitude
+    fn wrap(&self) -> Option<Wrap> {
+        None
+    }
+
+    /// the coordinate on the grid that `coordinate` stands for, itself unless the grid wraps
+    fn normalize(&self, coordinate: &Coordinate) -> Coordinate {
+        self.wrap()
+            .map_or(*coordinate, |wrap| wrap.normalize(coordinate))
+    }
+
+    /// the grid is centered on offset 0,0, see `Terrain::normalized_coords`
+    fn contains(&self, coordinate: &Coordinate) -> bool {
+        let offset: Offset = (&self.normalize(coordinate)).into();
+        let (first_column, last_column) = bounds(self.columns());
+        let (first_row, last_row) = bounds(self.rows());
+        (first_column..=last_column).contains(&offset.column())
+            && (first_row..=last_row).contains(&offset.row())
+    }
+
+    /// the closest coordinate on the grid, in row or column 0 if there are none
+    fn clamp(&self, coordinate: &Coordinate) -> Coordinate {
+        let offset: Offset = (&self.normalize(coordinate)).into();
+        let (first_column, last_column) = bounds(self.columns());
+        let (first_row, last_row) = bounds(self.rows());
+        // an empty grid ends before it starts
+        let (last_column, last_row) = (last_column.max(first_column), last_row.max(first_row));
+        Offset::new(
+            offset.column().clamp(first_column, last_column),
+            offset.row().clamp(first_row, last_row),
+        )
+        .into()
+    }
+
+    /// `range` without the coordinates that are off the grid
+    fn clip(&self, range: &Range) -> Range {
+        range
+            .iter()
+            .map(|coordinate| self.normalize(&coordinate))
+            .filter(|coordinate| self.contains(coordinate))
+            .collect()
+    }
+
+    /// every coordinate on the grid
+    fn coordinates(&self) -> Range {
+        if self.rows() == 0 || self.columns() == 0 {
+            return Range::default();
+        }
+        let (first_column, last_column) = bounds(self.columns());
+        let (first_row, last_row) = bounds(self.rows());
+        Range::rectangle(
+            &Offset::new(first_column, first_row).into(),
+            &Offset::new(last_column, last_row).into(),
+        )
+    }
+
+    /// like `Dist::dist`, but around the world if the grid wraps
+    fn distance(&self, a: &Coordinate, b: &Coordinate) -> u32 {
+        match self.wrap() {
+            Some(wrap) => a.dist_wrapped(b, &wrap),
+            None => a.dist(b),
+        }
+    }
 }
 
 pub trait Minimap<T>: GetByCoordinate<T> + WithGrid {
     fn minimap(&self, width: u16, height: u16) -> Vec<T> {
+        self.minimap_around(width, height, 0)
+    }
+
+    /// the minimap with `center_column` in the middle, wrapped grids continue past the seam
+    fn minimap_around(&self, width: u16, height: u16, center_column: i32) -> Vec<T> {
         // with_capacity does not work in godot context for some reason
         let mut minimap: Vec<T> = Vec::new();
         let scale_x = self.columns() as f64 / (width as f64);
@@ -68,11 +145,61 @@ pub trait Minimap<T>: GetByCoordinate<T> + WithGrid {
             let row = (y as f64 * scale_y) as i32;
             for x in -width_half..width_half {
                 let idx = row_id + (x + width_half) as usize;
-                let column = (x as f64 * scale_x) as i32;
+                let column = (x as f64 * scale_x) as i32 + center_column;
                 let coordinate: Coordinate = Offset::new(column, row).into();
-                minimap.insert(idx as usize, self.get(&coordinate));
+                minimap.insert(idx as usize, self.get(&self.normalize(&coordinate)));
             }
         }
         minimap
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// rows and columns
+    struct Grid(usize, usize);
+
+    impl WithGrid for Grid {
+        fn rows(&self) -> usize {
+            self.0
+        }
+
+        fn columns(&self) -> usize {
+            self.1
+        }
+    }
+
+    #[test]
+    fn test_bounds() {
+        let grid = Grid(4, 5);
+        let coordinates = grid.coordinates();
+        assert_eq!(coordinates.len(), 4 * 5);
+        assert!(coordinates.iter().all(|c| grid.contains(&c)));
+
+        let corner: Coordinate = Offset::new(2, 1).into();
+        assert!(grid.contains(&corner));
+        assert!(!grid.contains(&Offset::new(3, 1).into()));
+        assert_eq!(grid.clamp(&Offset::new(7, 9).into()), corner);
+        assert_eq!(grid.clamp(&corner), corner);
+
+        let clipped = grid.clip(&Range::circle(&corner, 1));
+        assert!(clipped.contains(&corner));
+        assert!(clipped.iter().all(|c| grid.contains(&c)));
+        assert!(clipped.len() < 7);
+    }
+
+    #[test]
+    fn test_empty_grid() {
+        let origin: Coordinate = Offset::new(0, 0).into();
+        for grid in [Grid(0, 5), Grid(4, 0), Grid(0, 0)].iter() {
+            assert!(grid.coordinates().is_empty());
+            assert!(!grid.contains(&origin));
+            let clamped: Offset = (&grid.clamp(&Offset::new(7, 9).into())).into();
+            assert!(grid.rows() > 0 || clamped.row() == 0);
+            assert!(grid.columns() > 0 || clamped.column() == 0);
+            assert!(grid.clip(&Range::circle(&origin, 1)).is_empty());
+        }
+    }
+}
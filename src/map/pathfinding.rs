@@ -0,0 +1,206 @@
+use crate::coordinate::range::{Range, RangeFactory};
+use crate::coordinate::Coordinate;
+use crate::map::minimap::{GetByCoordinate, WithGrid};
+use crate::map::terrain::TerrainType;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use strum::IntoEnumIterator;
+
+/// The cost of entering a coordinate by its terrain, `None` is impassable.
+#[derive(Clone, PartialEq, Eq)]
+pub struct MovementCosts {
+    costs: Vec<Option<u32>>,
+}
+
+impl MovementCosts {
+    /// hills, forests and marshes are slow, mountains and water can't be crossed
+    pub fn land() -> Self {
+        let costs = TerrainType::iter()
+            .map(|terrain_type| match terrain_type {
+                _ if terrain_type.is_water() || terrain_type.is_mountain() => None,
+                TerrainType::WoodedHills | TerrainType::TaigaHills => Some(4),
+                _ if terrain_type.is_hill() => Some(3),
+                TerrainType::Marsh | TerrainType::TundraMarsh => Some(3),
+                TerrainType::Taiga => Some(2),
+                _ if terrain_type.is_wooded() => Some(2),
+                _ => Some(1),
+            })
+            .collect();
+        MovementCosts { costs }
+    }
+
+    /// ships can only go where there is water
+    pub fn water() -> Self {
+        let costs = TerrainType::iter()
+            .map(|terrain_type| {
+                if terrain_type.is_water() {
+                    Some(1)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        MovementCosts { costs }
+    }
+
+    pub fn with_cost(mut self, terrain_type: TerrainType, cost: Option<u32>) -> Self {
+        self.costs[terrain_type as usize] = cost;
+        self
+    }
+
+    pub fn cost(&self, terrain_type: &TerrainType) -> Option<u32> {
+        self.costs[*terrain_type as usize]
+    }
+
+    /// the cheapest passable terrain, scales the heuristic so it never overestimates
+    fn min_cost(&self) -> u32 {
+        self.costs.iter().flatten().copied().min().unwrap_or(1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    coordinates: Vec<Coordinate>,
+    cost: u32,
+}
+
+impl Path {
+    /// from start to goal, including both
+    pub fn coordinates(&self) -> &Vec<Coordinate> {
+        &self.coordinates
+    }
+
+    /// the summed cost of every coordinate entered along the way
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+}
+
+/// A* from `start` to `goal`, `None` if the goal can't be reached within the map
+pub fn find_path<M>(
+    map: &M,
+    costs: &MovementCosts,
+    start: &Coordinate,
+    goal: &Coordinate,
+) -> Option<Path>
+where
+    M: GetByCoordinate<TerrainType> + WithGrid,
+{
+    if !map.contains(start) || !map.contains(goal) {
+        return None;
+    }
+    // on a wrapped map, both sides of the seam are the same coordinate
+    let (start, goal) = (&map.normalize(start), &map.normalize(goal));
+    // terrain is generated on the fly, so only look at every coordinate once
+    let mut entry_costs: HashMap<Coordinate, Option<u32>> = HashMap::new();
+    let mut entry_cost = |coordinate: &Coordinate| -> Option<u32> {
+        *entry_costs
+            .entry(*coordinate)
+            .or_insert_with(|| costs.cost(&map.get(coordinate)))
+    };
+    entry_cost(goal)?;
+
+    let heuristic = |coordinate: &Coordinate| map.distance(coordinate, goal) * costs.min_cost();
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<Coordinate, Coordinate> = HashMap::new();
+    let mut best: HashMap<Coordinate, u32> = HashMap::new();
+    best.insert(*start, 0);
+    open.push(Reverse((heuristic(start), *start)));
+
+    while let Some(Reverse((_, current))) = open.pop() {
+        let cost = best[&current];
+        if &current == goal {
+            let mut coordinates = vec![current];
+            let mut previous = current;
+            while let Some(next) = came_from.get(&previous) {
+                coordinates.push(*next);
+                previous = *next;
+            }
+            coordinates.reverse();
+            return Some(Path { coordinates, cost });
+        }
+        let mut neighbors: Vec<Coordinate> = Range::neighbors(&current)
+            .into_iter()
+            .map(|neighbor| map.normalize(&neighbor))
+            .collect();
+        // the range is unordered, sort for reproducible paths
+        neighbors.sort();
+        for neighbor in neighbors {
+            if !map.contains(&neighbor) {
+                continue;
+            }
+            let neighbor_cost = match entry_cost(&neighbor) {
+                Some(entry) => cost + entry,
+                None => continue,
+            };
+            if best
+                .get(&neighbor)
+                .map_or(false, |known| *known <= neighbor_cost)
+            {
+                continue;
+            }
+            best.insert(neighbor, neighbor_cost);
+            came_from.insert(neighbor, current);
+            open.push(Reverse((neighbor_cost + heuristic(&neighbor), neighbor)));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// grassland with a few exceptions
+    struct TestMap(HashMap<Coordinate, TerrainType>);
+
+    impl GetByCoordinate<TerrainType> for TestMap {
+        fn get(&self, coordinate: &Coordinate) -> TerrainType {
+            *self.0.get(coordinate).unwrap_or(&TerrainType::Grassland)
+        }
+    }
+
+    impl WithGrid for TestMap {
+        fn rows(&self) -> usize {
+            10
+        }
+
+        fn columns(&self) -> usize {
+            10
+        }
+    }
+
+    #[test]
+    fn test_find_path() {
+        // a mountain wall across the whole map with a single pass through the hills
+        let mut terrain = HashMap::new();
+        for y in -4..=5 {
+            terrain.insert(Coordinate::new(1, y), TerrainType::Mountain);
+        }
+        terrain.insert(Coordinate::new(1, 0), TerrainType::Hills);
+        let map = TestMap(terrain);
+        let land = MovementCosts::land();
+        let start = Coordinate::new(0, 0);
+        let goal = Coordinate::new(2, 0);
+
+        let path = find_path(&map, &land, &start, &goal).unwrap();
+        assert_eq!(path.coordinates().first(), Some(&start));
+        assert_eq!(path.coordinates().last(), Some(&goal));
+        assert!(path
+            .coordinates()
+            .iter()
+            .all(|coordinate| land.cost(&map.get(coordinate)).is_some()));
+        assert_eq!(path.cost(), 3 + 1);
+
+        let walled = land.with_cost(TerrainType::Hills, None);
+        assert!(find_path(&map, &walled, &start, &goal).is_none());
+        assert!(find_path(&map, &MovementCosts::water(), &start, &goal).is_none());
+        assert!(find_path(
+            &map,
+            &MovementCosts::land(),
+            &start,
+            &Coordinate::new(20, 0)
+        )
+        .is_none());
+    }
+}
@@ -1,42 +1,102 @@
 pub mod latlon;
+pub mod rivers;
+mod surface;
+mod terrain_cache;
 mod terrain_factory;
+pub mod terrain_rules;
 
+use crate::clock::calendar::Month;
+use crate::coordinate::range::Range;
+use crate::coordinate::wrap::Wrap;
 use crate::coordinate::{Coordinate, Offset};
 use crate::map::minimap::{GetByCoordinate, Minimap, WithGrid};
+use crate::saturating_from::SaturatingInto;
 pub use latlon::{Latitude, Longitude};
-use noise::{NoiseFn, Perlin, Seedable};
+use noise::{Perlin, Seedable};
+use rivers::Rivers;
+use std::sync::Arc;
+pub use surface::Surface;
+use terrain_cache::{TerrainCache, CACHED_CHUNKS};
 use terrain_factory::TerrainFactory;
-pub use terrain_factory::{Elevation, Moisture, TerrainMeta, TerrainType, TerrainYields};
+pub use terrain_factory::{
+    Elevation, Moisture, Temperature, TerrainMeta, TerrainType, TerrainYields,
+};
+pub use terrain_rules::TerrainRules;
 
 pub struct Terrain {
     rows: usize,
     columns: usize,
     tile_factory: TerrainFactory,
     random_latitude: Perlin,
+    wrap: Option<Wrap>,
+    surface: Surface,
+    cache: TerrainCache,
+    rivers: Rivers,
 }
 
 impl Terrain {
-    pub fn new_seeded(seed: u32, rows: usize, columns: usize, island_noise: f64) -> Self {
-        let random_latitude = Perlin::new().set_seed(7 * seed);
-        Terrain {
+    fn create(
+        seed: u32,
+        rows: usize,
+        columns: usize,
+        island_noise: f64,
+        wrap: Option<Wrap>,
+        rules: Arc<TerrainRules>,
+    ) -> Self {
+        let random_latitude = Perlin::new().set_seed(seed.wrapping_mul(7));
+        let surface = if wrap.is_some() {
+            Surface::Cylinder
+        } else {
+            Surface::Plane
+        };
+        let mut terrain = Terrain {
             rows,
             columns,
             random_latitude,
-            tile_factory: TerrainFactory::new(seed, island_noise),
-        }
+            tile_factory: TerrainFactory::new(seed, island_noise, surface, rules),
+            wrap,
+            surface,
+            cache: TerrainCache::new(CACHED_CHUNKS),
+            rivers: Rivers::default(),
+        };
+        terrain.rivers = Rivers::trace(&terrain, |coordinate| {
+            let (nx, ny) = terrain.normalized_coords(coordinate);
+            let (elevation, terrain_type) = terrain.tile_factory.create_surface(nx, ny);
+            (elevation.into(), terrain_type.is_water())
+        });
+        terrain
+    }
+
+    pub fn new_seeded(
+        seed: u32,
+        rows: usize,
+        columns: usize,
+        island_noise: f64,
+        rules: Arc<TerrainRules>,
+    ) -> Self {
+        Terrain::create(seed, rows, columns, island_noise, None, rules)
     }
 
-    pub fn new(rows: usize, columns: usize, island_noise: f64) -> Self {
-        Terrain::new_seeded(1234, rows, columns, island_noise)
+    /// a world without east and west edge, it keeps them with an odd number of columns, see
+    /// `Configuration::with_wrapped_longitude`
+    pub fn new_wrapped_seeded(
+        seed: u32,
+        rows: usize,
+        columns: usize,
+        island_noise: f64,
+        rules: Arc<TerrainRules>,
+    ) -> Self {
+        let wrap = Wrap::new(columns);
+        Terrain::create(seed, rows, columns, island_noise, wrap, rules)
     }
 
     fn smudge_latitude(&self, x: f64, y: f64) -> f64 {
-        y + (self.random_latitude.get([x * 4., y * 4.]) * y.abs().max(0.1)) / 10.
+        y + (self.surface.sample(&self.random_latitude, x, y, 4.) * y.abs().max(0.1)) / 10.
     }
 
     // https://www.redblobgames.com/maps/terrain-from-noise/#islands
     fn normalized_coords(&self, coordinate: &Coordinate) -> (f64, f64) {
-        let offset: Offset = coordinate.into();
+        let offset: Offset = (&self.normalize(coordinate)).into();
         // offset 0,0 to middle of width/height
         let x = offset.column() as f64 + self.columns() as f64 / 2.;
         let y = offset.row() as f64 + self.rows() as f64 / 2.;
@@ -45,6 +105,38 @@ impl Terrain {
         let smudged_ny = self.smudge_latitude(nx, true_ny);
         (nx, smudged_ny)
     }
+
+    /// the terrain without going through the cache, `coordinate` has to be on the grid
+    fn generate(&self, coordinate: &Coordinate) -> TerrainMeta {
+        let (nx, ny) = self.normalized_coords(coordinate);
+        self.tile_factory
+            .create(nx, ny, self.rivers.strength(coordinate))
+    }
+
+    /// generate the terrain of `range` in parallel, e.g. before the camera pans there
+    pub fn prefetch(&self, range: &Range) {
+        let generate = |coordinate: &Coordinate| self.generate(coordinate);
+        self.cache.prefetch(self.clip(range).iter(), &generate);
+    }
+
+    /// mountains block the line of sight, e.g. for `line::sight_line`
+    pub fn blocks_sight(&self, coordinate: &Coordinate) -> bool {
+        GetByCoordinate::<TerrainType>::get(self, coordinate).is_mountain()
+    }
+
+    /// the terrain with the harvest yields of the given month, off the grid it is the closest
+    /// coordinate on the grid like for `get`
+    pub fn get_in(&self, coordinate: &Coordinate, month: Month) -> TerrainMeta {
+        let coordinate = self.clamp(coordinate);
+        let (_, ny) = self.normalized_coords(&coordinate);
+        let latitude: Latitude = ny.saturating_into();
+        GetByCoordinate::<TerrainMeta>::get(self, &coordinate).in_month(&latitude, &month)
+    }
+
+    /// the rivers along the edges between coordinates
+    pub fn rivers(&self) -> &Rivers {
+        &self.rivers
+    }
 }
 
 impl WithGrid for Terrain {
@@ -55,20 +147,83 @@ impl WithGrid for Terrain {
     fn columns(&self) -> usize {
         self.columns
     }
+
+    fn wrap(&self) -> Option<Wrap> {
+        self.wrap
+    }
 }
 
+/// nothing is generated off the grid, the closest coordinate on it stands in instead
 impl GetByCoordinate<TerrainMeta> for Terrain {
     fn get(&self, coordinate: &Coordinate) -> TerrainMeta {
-        let (nx, ny) = self.normalized_coords(coordinate);
-        self.tile_factory.create(nx, ny)
+        let generate = |coordinate: &Coordinate| self.generate(coordinate);
+        self.cache
+            .read(&self.clamp(coordinate), &generate, TerrainMeta::clone)
     }
 }
 
 impl GetByCoordinate<TerrainType> for Terrain {
     fn get(&self, coordinate: &Coordinate) -> TerrainType {
-        let (nx, ny) = self.normalized_coords(coordinate);
-        self.tile_factory.create_terrain_type(nx, ny)
+        let generate = |coordinate: &Coordinate| self.generate(coordinate);
+        self.cache.read(
+            &self.clamp(coordinate),
+            &generate,
+            TerrainMeta::terrain_type,
+        )
     }
 }
 
 impl Minimap<TerrainType> for Terrain {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinate::range::RangeFrom;
+    use crate::good::Good;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_bounds() {
+        let terrain = Terrain::new_seeded(3, 10, 10, 4., Default::default());
+        let at = |coordinate: &Coordinate| -> TerrainType { terrain.get(coordinate) };
+        let corner: Coordinate = Offset::new(4, 4).into();
+        let outside: Coordinate = Offset::new(9, 7).into();
+        assert!(at(&outside) == at(&corner));
+        assert!(
+            terrain.get_in(&outside, Month::July).elevation()
+                == terrain.get_in(&corner, Month::July).elevation()
+        );
+
+        // nothing off the grid ends up in the cache
+        terrain.coordinates().iter().for_each(|coordinate| {
+            at(&coordinate);
+        });
+        let chunks = terrain.cache.len();
+        terrain.prefetch(&Coordinate::from(Offset::new(40, 40)).circle(3));
+        at(&Offset::new(-40, 40).into());
+        assert_eq!(terrain.cache.len(), chunks);
+    }
+
+    #[test]
+    fn test_seasonal_yields() {
+        let terrain = Terrain::new_seeded(3, 100, 100, 4., Default::default());
+        let ears = |coordinate: &Coordinate, month: Month| -> f64 {
+            terrain
+                .get_in(coordinate, month)
+                .yields()
+                .get(&Good::Ears())
+                .map_or(0., |amount| amount.percent())
+        };
+        let seasonal = terrain
+            .coordinates()
+            .iter()
+            .find(|coordinate| ears(coordinate, Month::January) != ears(coordinate, Month::July))
+            .unwrap();
+        // the seasons are applied to the cached yields of the whole year
+        let chunks = terrain.cache.len();
+        for month in Month::iter() {
+            ears(&seasonal, month);
+        }
+        assert_eq!(terrain.cache.len(), chunks);
+    }
+}
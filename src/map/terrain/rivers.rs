@@ -0,0 +1,333 @@
+use crate::coordinate::dist::Dist;
+use crate::coordinate::faces::{Face, Faces};
+use crate::coordinate::range::{ring_coordinates, Range, RangeFactory};
+use crate::coordinate::wrap::Wrap;
+use crate::coordinate::Coordinate;
+use crate::map::minimap::WithGrid;
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+/// how many corners have to drain into an edge before it carries a river
+const MIN_FLOW: u32 = 10;
+
+/// A corner where three coordinates meet, sorted so every corner has a single key.
+type Corner = [Coordinate; 3];
+/// An edge between two neighboring coordinates, sorted as well.
+type Edge = (Coordinate, Coordinate);
+
+fn corner(a: Coordinate, b: Coordinate, c: Coordinate) -> Corner {
+    let mut corner = [a, b, c];
+    corner.sort();
+    corner
+}
+
+fn edge(a: Coordinate, b: Coordinate) -> Edge {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct River {
+    flow: u32,
+}
+
+impl River {
+    /// how many corners upstream drain through here
+    pub fn flow(&self) -> u32 {
+        self.flow
+    }
+
+    /// 1 for a creek up to 3 for a stream, doubling the flow for every step
+    pub fn width(&self) -> u8 {
+        match self.flow / MIN_FLOW {
+            0..=1 => 1,
+            2..=3 => 2,
+            _ => 3,
+        }
+    }
+}
+
+/// Rivers run along the edges between coordinates, from corner to corner downhill until they
+/// reach a corner at the ocean or a lake.
+#[derive(Default)]
+pub struct Rivers {
+    edges: HashMap<Edge, River>,
+    // the widest river along any face of a coordinate
+    by_coordinate: HashMap<Coordinate, River>,
+    wrap: Option<Wrap>,
+}
+
+struct Cell {
+    elevation: f64,
+    is_water: bool,
+}
+
+impl Rivers {
+    /// trace the rivers on the grid of `map`, `cell` tells the elevation and if there is water
+    pub fn trace<M: WithGrid + Sync>(
+        map: &M,
+        cell: impl Fn(&Coordinate) -> (f64, bool) + Sync,
+    ) -> Self {
+        let cells: HashMap<Coordinate, Cell> = map
+            .coordinates()
+            .iter()
+            .collect::<Vec<Coordinate>>()
+            .into_par_iter()
+            .map(|coordinate| {
+                let (elevation, is_water) = cell(&coordinate);
+                (
+                    coordinate,
+                    Cell {
+                        elevation,
+                        is_water,
+                    },
+                )
+            })
+            .collect();
+        let on_grid = |coordinate: &Coordinate| -> Option<Coordinate> {
+            Some(map.normalize(coordinate)).filter(|coordinate| map.contains(coordinate))
+        };
+        // the lap of `coordinate` next to `to`, across the seam if that is closer
+        let closest = |coordinate: &Coordinate, to: &Coordinate| -> Coordinate {
+            map.wrap().map_or(*coordinate, |wrap| {
+                *wrap
+                    .neighboring_laps(coordinate)
+                    .iter()
+                    .min_by_key(|lap| lap.dist(to))
+                    .unwrap()
+            })
+        };
+        let elevation = |corner: &Corner| -> f64 {
+            corner.iter().map(|c| cells[c].elevation).sum::<f64>() / 3.
+        };
+        let touches_water = |corner: &Corner| corner.iter().any(|c| cells[c].is_water);
+
+        // every corner with all three of its coordinates on the grid, once from its first one
+        let corners: Vec<Corner> = cells
+            .par_iter()
+            .flat_map_iter(|(coordinate, _)| {
+                let ring = ring_coordinates(coordinate, 1);
+                (0..ring.len())
+                    .filter_map(|idx| {
+                        let first = on_grid(&ring[idx])?;
+                        let second = on_grid(&ring[(idx + 1) % ring.len()])?;
+                        Some(corner(*coordinate, first, second))
+                    })
+                    .filter(|corner| corner[0] == *coordinate)
+                    .collect::<Vec<Corner>>()
+            })
+            .collect();
+
+        // water flows to the lowest neighboring corner, if there is a lower one
+        let downstream: HashMap<Corner, (Corner, Edge)> = corners
+            .par_iter()
+            .filter(|corner| !touches_water(corner))
+            .filter_map(|current| {
+                let mut lowest: Option<(f64, Corner, Edge)> = None;
+                for (a, b, c) in [
+                    (current[0], current[1], current[2]),
+                    (current[0], current[2], current[1]),
+                    (current[1], current[2], current[0]),
+                ]
+                .iter()
+                {
+                    // the other corner along the edge between a and b is mirrored across it
+                    let next = match on_grid(&(a + closest(b, a) - closest(c, a))) {
+                        Some(other) => corner(*a, *b, other),
+                        None => continue,
+                    };
+                    let next_elevation = elevation(&next);
+                    let is_lower = match &lowest {
+                        Some((lowest_elevation, lowest_corner, _)) => {
+                            (next_elevation, next) < (*lowest_elevation, *lowest_corner)
+                        }
+                        None => true,
+                    };
+                    if is_lower {
+                        lowest = Some((next_elevation, next, edge(*a, *b)));
+                    }
+                }
+                let (lowest_elevation, next, edge) = lowest?;
+                Some((*current, (next, edge))).filter(|_| lowest_elevation < elevation(current))
+            })
+            .collect();
+
+        // only rivers that make it to the water, the others would end in a hollow
+        let mut drains: HashMap<Corner, bool> = HashMap::new();
+        for start in corners.iter() {
+            let mut chain = vec![];
+            let mut current = *start;
+            let reaches_water = loop {
+                if let Some(known) = drains.get(&current) {
+                    break *known;
+                }
+                if touches_water(&current) {
+                    break true;
+                }
+                chain.push(current);
+                match downstream.get(&current) {
+                    Some((next, _)) => current = *next,
+                    None => break false,
+                }
+            };
+            for corner in chain {
+                drains.insert(corner, reaches_water);
+            }
+        }
+
+        // every corner gets the same rain, passed on from the highest corners down
+        let mut sorted: Vec<(f64, Corner)> = downstream
+            .keys()
+            .map(|corner| (elevation(corner), *corner))
+            .collect();
+        sorted.sort_by(|a, b| b.partial_cmp(a).unwrap());
+        let mut flows: HashMap<Corner, u32> = HashMap::new();
+        let mut rivers = Rivers {
+            wrap: map.wrap(),
+            ..Default::default()
+        };
+        for (_, current) in sorted {
+            let flow = *flows.entry(current).or_insert(0) + 1;
+            let (next, edge) = downstream[&current];
+            *flows.entry(next).or_insert(0) += flow;
+            if flow >= MIN_FLOW && drains[&current] {
+                rivers.insert(edge, River { flow });
+            }
+        }
+        rivers
+    }
+
+    fn normalize(&self, coordinate: &Coordinate) -> Coordinate {
+        self.wrap
+            .map_or(*coordinate, |wrap| wrap.normalize(coordinate))
+    }
+
+    fn insert(&mut self, edge: Edge, river: River) {
+        for coordinate in [edge.0, edge.1].iter() {
+            let widest = self.by_coordinate.entry(*coordinate).or_insert(river);
+            if widest.flow < river.flow {
+                *widest = river;
+            }
+        }
+        self.edges.insert(edge, river);
+    }
+
+    /// the widest river along any face of `coordinate`
+    pub fn at(&self, coordinate: &Coordinate) -> Option<River> {
+        self.by_coordinate.get(&self.normalize(coordinate)).copied()
+    }
+
+    /// the river along one `face` of `coordinate`
+    pub fn at_face(&self, coordinate: &Coordinate, face: &Face) -> Option<River> {
+        let neighbor = self.normalize(&coordinate.neighbor_at(face)?);
+        self.edges
+            .get(&edge(self.normalize(coordinate), neighbor))
+            .copied()
+    }
+
+    /// 0 without a river along `coordinate`, up to 1 along the widest rivers
+    pub fn strength(&self, coordinate: &Coordinate) -> f64 {
+        self.at(coordinate)
+            .map_or(0., |river| river.width() as f64 / 3.)
+    }
+
+    /// the faces of `coordinate` with a river, e.g. for the rules placing buildings along them
+    pub fn faces(&self, coordinate: &Coordinate) -> Vec<(Face, River)> {
+        let mut neighbors: Vec<Coordinate> = Range::neighbors(coordinate).into_iter().collect();
+        neighbors.sort();
+        neighbors
+            .into_iter()
+            .filter_map(|neighbor| {
+                let river = self
+                    .edges
+                    .get(&edge(self.normalize(coordinate), self.normalize(&neighbor)))?;
+                Some((coordinate.touching_face(&neighbor), *river))
+            })
+            .collect()
+    }
+
+    /// how many edges carry a river
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinate::Offset;
+
+    struct Grid;
+
+    impl WithGrid for Grid {
+        fn rows(&self) -> usize {
+            20
+        }
+
+        fn columns(&self) -> usize {
+            20
+        }
+    }
+
+    struct WrappedGrid;
+
+    impl WithGrid for WrappedGrid {
+        fn rows(&self) -> usize {
+            20
+        }
+
+        fn columns(&self) -> usize {
+            20
+        }
+
+        fn wrap(&self) -> Option<Wrap> {
+            Wrap::new(20)
+        }
+    }
+
+    #[test]
+    fn test_rivers() {
+        // a valley sloping down to the sea in the south
+        let cell = |coordinate: &Coordinate| {
+            let offset: Offset = coordinate.into();
+            let height = (6 - offset.row()) as f64 + 0.5 * offset.column().abs() as f64;
+            (height + 0.01 * offset.column() as f64, offset.row() > 6)
+        };
+        let rivers = Rivers::trace(&Grid, cell);
+        assert!(!rivers.is_empty());
+        for ((a, b), river) in rivers.edges.iter() {
+            assert!(river.flow() >= MIN_FLOW);
+            assert!(!cell(a).1 || !cell(b).1);
+            let face = a.touching_face(b);
+            assert_eq!(rivers.at_face(a, &face), Some(*river));
+            assert!(rivers.faces(a).contains(&(face, *river)));
+            assert!(rivers.at(a).unwrap().flow() >= river.flow());
+        }
+        // the river gets wider towards the sea
+        let widest = rivers.edges.values().map(River::width).max().unwrap();
+        assert!(widest > 1);
+        let same = Rivers::trace(&Grid, cell);
+        assert_eq!(same.edges, rivers.edges);
+
+        // the same valley along the seam, its rivers cross it
+        let seam_cell = |coordinate: &Coordinate| {
+            let offset: Offset = coordinate.into();
+            let height = (6 - offset.row()) as f64 + 0.5 * (10 - offset.column().abs()) as f64;
+            (height + 0.01 * offset.column() as f64, offset.row() > 6)
+        };
+        let wrap = WrappedGrid.wrap().unwrap();
+        let wrapped = Rivers::trace(&WrappedGrid, seam_cell);
+        assert!(wrapped
+            .edges
+            .keys()
+            .all(|(a, b)| a.dist_wrapped(b, &wrap) == 1));
+        assert!(wrapped.edges.keys().any(|(a, b)| a.dist(b) > 1));
+    }
+}
@@ -0,0 +1,34 @@
+use noise::NoiseFn;
+use std::f64::consts::PI;
+
+/// How the normalized coordinates are laid onto the noise.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Surface {
+    Plane,
+    /// nx -1 and 1 meet, so there is no seam on worlds that wrap along the longitude
+    Cylinder,
+}
+
+impl Default for Surface {
+    fn default() -> Self {
+        Surface::Plane
+    }
+}
+
+impl Surface {
+    /// sample `noise` at `nx`, `ny` scaled by `frequency`
+    pub fn sample<N>(&self, noise: &N, nx: f64, ny: f64, frequency: f64) -> f64
+    where
+        N: NoiseFn<[f64; 2]> + NoiseFn<[f64; 3]>,
+    {
+        match self {
+            Surface::Plane => noise.get([nx * frequency, ny * frequency]),
+            Surface::Cylinder => {
+                // the circumference is as long as the plane is wide, so features keep their size
+                let angle = nx * PI;
+                let radius = frequency / PI;
+                noise.get([angle.cos() * radius, ny * frequency, angle.sin() * radius])
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::coordinate::{Coordinate, Offset};
+use crate::map::terrain::TerrainMeta;
+use crate::stacked_lru::StackedLRU;
+use rayon::prelude::*;
+use std::sync::Arc;
+
+/// a chunk covers 16x16 offset coordinates
+const CHUNK_SIZE: i32 = 16;
+/// at most this many chunks are kept whatever the size of the map, that is 256x256 offset
+/// coordinates, several times what the viewport shows and prefetches around it
+pub const CACHED_CHUNKS: usize = 256;
+
+type ChunkKey = (i32, i32);
+type Chunk = Vec<TerrainMeta>;
+
+fn chunk_of(coordinate: &Coordinate) -> (ChunkKey, usize) {
+    let offset: Offset = coordinate.into();
+    let key = (
+        offset.column().div_euclid(CHUNK_SIZE),
+        offset.row().div_euclid(CHUNK_SIZE),
+    );
+    let idx =
+        offset.row().rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + offset.column().rem_euclid(CHUNK_SIZE);
+    (key, idx as usize)
+}
+
+fn coordinates_of(key: &ChunkKey) -> impl IndexedParallelIterator<Item = Coordinate> {
+    let (first_column, first_row) = (key.0 * CHUNK_SIZE, key.1 * CHUNK_SIZE);
+    (0..CHUNK_SIZE * CHUNK_SIZE)
+        .into_par_iter()
+        .map(move |idx| {
+            Offset::new(
+                first_column + idx % CHUNK_SIZE,
+                first_row + idx / CHUNK_SIZE,
+            )
+            .into()
+        })
+}
+
+/// Terrain generated a chunk at a time, each chunk in parallel, and kept until it is the least
+/// recently used one.
+pub struct TerrainCache {
+    chunks: StackedLRU<ChunkKey, Chunk>,
+}
+
+impl TerrainCache {
+    pub fn new(capacity: usize) -> Self {
+        TerrainCache {
+            chunks: StackedLRU::new(capacity),
+        }
+    }
+
+    fn chunk(
+        &self,
+        key: ChunkKey,
+        generate: &(impl Fn(&Coordinate) -> TerrainMeta + Sync),
+    ) -> Arc<Chunk> {
+        self.chunks.reference(key, |key| {
+            coordinates_of(key)
+                .map(|coordinate| generate(&coordinate))
+                .collect()
+        })
+    }
+
+    /// read the cached terrain at `coordinate`, its whole chunk is generated if it isn't cached
+    pub fn read<T>(
+        &self,
+        coordinate: &Coordinate,
+        generate: &(impl Fn(&Coordinate) -> TerrainMeta + Sync),
+        read: impl FnOnce(&TerrainMeta) -> T,
+    ) -> T {
+        let (key, idx) = chunk_of(coordinate);
+        read(&self.chunk(key, generate)[idx])
+    }
+
+    /// generate the chunks of all `coordinates` up front, side by side
+    pub fn prefetch(
+        &self,
+        coordinates: impl Iterator<Item = Coordinate>,
+        generate: &(impl Fn(&Coordinate) -> TerrainMeta + Sync),
+    ) {
+        let mut keys: Vec<ChunkKey> = coordinates.map(|c| chunk_of(&c).0).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        keys.into_par_iter()
+            .filter(|key| !self.chunks.contains_key(key))
+            .for_each(|key| {
+                self.chunk(key, generate);
+            });
+    }
+
+    /// in chunks
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_terrain_cache() {
+        let generated = AtomicUsize::new(0);
+        let generate = |_: &Coordinate| {
+            generated.fetch_add(1, Ordering::SeqCst);
+            TerrainMeta::default()
+        };
+        let cache = TerrainCache::new(4);
+        let chunk_len = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+        cache.read(
+            &Offset::new(-1, -1).into(),
+            &generate,
+            TerrainMeta::elevation,
+        );
+        assert_eq!(generated.load(Ordering::SeqCst), chunk_len);
+        cache.read(
+            &Offset::new(-16, -16).into(),
+            &generate,
+            TerrainMeta::elevation,
+        );
+        assert_eq!(generated.load(Ordering::SeqCst), chunk_len);
+
+        let coordinates = (-16..16).map(|column| Offset::new(column, 0).into());
+        cache.prefetch(coordinates, &generate);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(generated.load(Ordering::SeqCst), 3 * chunk_len);
+
+        for row in 0..10 {
+            cache.read(
+                &Offset::new(0, row * CHUNK_SIZE).into(),
+                &generate,
+                TerrainMeta::elevation,
+            );
+        }
+        assert!(cache.len() <= 4);
+    }
+
+    #[test]
+    fn test_eviction() {
+        let generate = |_: &Coordinate| TerrainMeta::default();
+        let cache = TerrainCache::new(4);
+        let read = |chunk: i32| {
+            cache.read(
+                &Offset::new(0, chunk * CHUNK_SIZE).into(),
+                &generate,
+                TerrainMeta::elevation,
+            );
+        };
+        let cached = |chunk: i32| cache.chunks.contains_key(&(0, chunk));
+
+        (0..5).for_each(read);
+        // the first eviction picks any of the chunks that weren't read since
+        let survivors: Vec<i32> = (0..4).filter(|chunk| cached(*chunk)).collect();
+        assert_eq!(survivors.len(), 3);
+        assert!(cached(4));
+
+        // reading them again keeps them around, the one that wasn't goes next
+        read(survivors[0]);
+        read(survivors[1]);
+        read(5);
+        assert!(!cached(survivors[2]));
+        assert!([survivors[0], survivors[1], 4, 5]
+            .iter()
+            .all(|chunk| cached(*chunk)));
+        assert_eq!(cache.len(), 4);
+    }
+}
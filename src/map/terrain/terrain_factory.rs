@@ -1,23 +1,30 @@
 mod terrain_elevation;
 mod terrain_moisture;
+mod terrain_temperature;
 mod terrain_type;
 mod terrain_yields;
 
-use crate::map::terrain::{Latitude, Longitude};
+use crate::clock::calendar::Month;
+use crate::map::terrain::terrain_rules::TerrainRules;
+use crate::map::terrain::{Latitude, Longitude, Surface};
 use crate::saturating_from::SaturatingInto;
+use std::sync::Arc;
 pub use terrain_elevation::Elevation;
 use terrain_elevation::TerrainElevationFactory;
 pub use terrain_moisture::Moisture;
 use terrain_moisture::TerrainMoistureFactory;
+pub use terrain_temperature::Temperature;
+use terrain_temperature::TerrainTemperatureFactory;
 pub use terrain_type::TerrainType;
 use terrain_type::TerrainTypeFactory;
 pub use terrain_yields::TerrainYields;
-use terrain_yields::TerrainYieldsFactory;
+use terrain_yields::{TerrainYieldsFactory, YieldSite};
 
 #[derive(Default, Clone)]
 pub struct TerrainMeta {
     elevation: Elevation,
     moisture: Moisture,
+    temperature: Temperature,
     terrain_type: TerrainType,
     yields: TerrainYields,
 }
@@ -38,47 +45,84 @@ impl TerrainMeta {
     pub fn moisture(&self) -> Moisture {
         self.moisture
     }
+
+    pub fn temperature(&self) -> Temperature {
+        self.temperature
+    }
+
+    /// the same terrain with the yields of `month` at `latitude` instead of the whole year
+    pub fn in_month(&self, latitude: &Latitude, month: &Month) -> TerrainMeta {
+        TerrainMeta {
+            yields: terrain_yields::in_month(&self.yields, latitude, month),
+            ..self.clone()
+        }
+    }
 }
 
 pub struct TerrainFactory {
     elevation_factory: TerrainElevationFactory,
     moisture_factory: TerrainMoistureFactory,
+    temperature_factory: TerrainTemperatureFactory,
     yields_factory: TerrainYieldsFactory,
     type_factory: TerrainTypeFactory,
+    rules: Arc<TerrainRules>,
 }
 
 impl TerrainFactory {
-    pub fn new(seed: u32, island_noise: f64) -> Self {
+    pub fn new(seed: u32, island_noise: f64, surface: Surface, rules: Arc<TerrainRules>) -> Self {
         TerrainFactory {
-            elevation_factory: TerrainElevationFactory::new(seed, island_noise),
-            moisture_factory: TerrainMoistureFactory::new(seed * 3, island_noise * 4.),
-            yields_factory: TerrainYieldsFactory::new(seed * 4),
-            type_factory: TerrainTypeFactory::new(),
+            elevation_factory: TerrainElevationFactory::new(seed, island_noise, surface),
+            moisture_factory: TerrainMoistureFactory::new(
+                seed.wrapping_mul(3),
+                island_noise * 4.,
+                surface,
+            ),
+            temperature_factory: TerrainTemperatureFactory::new(
+                seed.wrapping_mul(5),
+                rules.clone(),
+                surface,
+            ),
+            yields_factory: TerrainYieldsFactory::new(seed.wrapping_mul(4), surface, rules.clone()),
+            type_factory: TerrainTypeFactory::new(rules.clone()),
+            rules,
         }
     }
 
-    // a quicker version for minimap and such
-    pub fn create_terrain_type(&self, nx: f64, ny: f64) -> TerrainType {
+    /// `river` is the strength of a river along the coordinate, 0 without one
+    pub fn create(&self, nx: f64, ny: f64, river: f64) -> TerrainMeta {
         let elevation = self.elevation_factory.create(nx, ny);
-        let moisture = self.moisture_factory.create(nx, ny);
-        let latitude: Latitude = ny.saturating_into();
-        self.type_factory.create(latitude, elevation, moisture)
-    }
-
-    pub fn create(&self, nx: f64, ny: f64) -> TerrainMeta {
-        let elevation = self.elevation_factory.create(nx, ny);
-        let moisture = self.moisture_factory.create(nx, ny);
+        let base_moisture: f64 = self.moisture_factory.create(nx, ny).into();
+        // a river waters its banks, but doesn't turn them into a lake
+        let moisture = (base_moisture + river * 0.3)
+            .min(base_moisture.max(self.rules.thresholds.freshwater_moisture - 0.01))
+            .saturating_into();
         let latitude: Latitude = ny.saturating_into();
         let longitude: Longitude = nx.saturating_into();
-        let terrain_type = self.type_factory.create(latitude, elevation, moisture);
-        let yields =
-            self.yields_factory
-                .create(latitude, longitude, elevation, moisture, &terrain_type);
+        let temperature = self.temperature_factory.create(nx, ny, latitude, elevation);
+        let terrain_type = self.type_factory.create(temperature, elevation, moisture);
+        let yields = self.yields_factory.create(&YieldSite {
+            latitude,
+            longitude,
+            moisture,
+            terrain_type,
+            river,
+        });
         TerrainMeta {
             elevation,
             moisture,
+            temperature,
             terrain_type,
             yields,
         }
     }
+
+    /// the elevation and terrain type only, enough to trace the rivers
+    pub fn create_surface(&self, nx: f64, ny: f64) -> (Elevation, TerrainType) {
+        let elevation = self.elevation_factory.create(nx, ny);
+        let moisture = self.moisture_factory.create(nx, ny);
+        let latitude: Latitude = ny.saturating_into();
+        let temperature = self.temperature_factory.create(nx, ny, latitude, elevation);
+        let terrain_type = self.type_factory.create(temperature, elevation, moisture);
+        (elevation, terrain_type)
+    }
 }
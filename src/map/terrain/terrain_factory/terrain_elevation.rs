@@ -1,6 +1,7 @@
+use crate::map::terrain::Surface;
 use crate::saturating_from::SaturatingInto;
 use derive_more::Into;
-use noise::{NoiseFn, Perlin, Seedable};
+use noise::{Perlin, Seedable};
 use std::cmp::Ordering;
 use std::ops::Mul;
 
@@ -28,32 +29,35 @@ impl SaturatingInto<Elevation> for f64 {
 pub struct TerrainElevationFactory {
     random_elevation: Perlin,
     island_noise: f64,
+    surface: Surface,
 }
 
 impl TerrainElevationFactory {
-    pub fn new(seed: u32, island_noise: f64) -> Self {
+    pub fn new(seed: u32, island_noise: f64, surface: Surface) -> Self {
         let random_elevation = Perlin::new().set_seed(seed);
         TerrainElevationFactory {
             random_elevation,
             island_noise,
+            surface,
         }
     }
 
-    fn random_elevation(&self, x: f64, y: f64) -> f64 {
-        (self.random_elevation.get([x, y]) + 1.) / 2.
+    fn random_elevation(&self, nx: f64, ny: f64, frequency: f64) -> f64 {
+        (self
+            .surface
+            .sample(&self.random_elevation, nx, ny, frequency)
+            + 1.)
+            / 2.
     }
 
     pub fn create(&self, nx: f64, ny: f64) -> Elevation {
-        let mut elevation: f64 = (self.random_elevation(nx, ny)
-            + self.random_elevation(self.island_noise * nx, self.island_noise * ny))
+        let mut elevation: f64 = (self.random_elevation(nx, ny, 1.)
+            + self.random_elevation(nx, ny, self.island_noise))
         .mul(0.5)
         .powf(3.);
         if elevation > 0.12 {
             elevation = self
-                .random_elevation(
-                    nx * self.island_noise.powf(2.),
-                    ny * self.island_noise.powf(2.),
-                )
+                .random_elevation(nx, ny, self.island_noise.powf(2.))
                 .powf(3.)
                 .max(0.12);
         }
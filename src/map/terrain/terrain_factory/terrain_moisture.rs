@@ -1,6 +1,7 @@
+use crate::map::terrain::Surface;
 use crate::saturating_from::SaturatingInto;
 use derive_more::Into;
-use noise::{NoiseFn, Perlin, Seedable};
+use noise::{Perlin, Seedable};
 use std::cmp::Ordering;
 use std::ops::Mul;
 
@@ -34,23 +35,29 @@ impl SaturatingInto<Moisture> for f64 {
 pub struct TerrainMoistureFactory {
     random_moisture: Perlin,
     moisture_noise: f64,
+    surface: Surface,
 }
 
 impl TerrainMoistureFactory {
-    pub fn new(seed: u32, moisture_noise: f64) -> Self {
+    pub fn new(seed: u32, moisture_noise: f64, surface: Surface) -> Self {
         let random_moisture = Perlin::new().set_seed(seed);
         TerrainMoistureFactory {
             random_moisture,
             moisture_noise,
+            surface,
         }
     }
 
-    fn random_moisture(&self, x: f64, y: f64) -> f64 {
-        (self.random_moisture.get([x, y]) + 1.) / 2.
+    fn random_moisture(&self, nx: f64, ny: f64) -> f64 {
+        (self
+            .surface
+            .sample(&self.random_moisture, nx, ny, self.moisture_noise)
+            + 1.)
+            / 2.
     }
 
     pub fn create(&self, nx: f64, ny: f64) -> Moisture {
-        self.random_moisture(self.moisture_noise * nx, self.moisture_noise * ny)
+        self.random_moisture(nx, ny)
             .mul(1.1)
             // tropics no desert
             .max(if ny.abs() < 0.083 { 0.1 } else { 0. })
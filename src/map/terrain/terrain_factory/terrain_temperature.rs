@@ -0,0 +1,112 @@
+use crate::map::terrain::latlon::LatLon;
+use crate::map::terrain::terrain_rules::TerrainRules;
+use crate::map::terrain::{Elevation, Latitude, Surface};
+use derive_more::Into;
+use noise::{Perlin, Seedable};
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+/// the yearly mean, in degrees celsius
+#[derive(PartialEq, PartialOrd, Copy, Clone, Default, Into)]
+pub struct Temperature(f64);
+
+impl Temperature {
+    pub const fn new(temperature: f64) -> Self {
+        Temperature(temperature)
+    }
+}
+
+impl PartialEq<f64> for Temperature {
+    fn eq(&self, other: &f64) -> bool {
+        Into::<f64>::into(*self).eq(other)
+    }
+}
+
+impl PartialOrd<f64> for Temperature {
+    fn partial_cmp(&self, other: &f64) -> Option<Ordering> {
+        Into::<f64>::into(*self).partial_cmp(other)
+    }
+}
+
+pub struct TerrainTemperatureFactory {
+    random_temperature: Perlin,
+    rules: Arc<TerrainRules>,
+    surface: Surface,
+}
+
+impl TerrainTemperatureFactory {
+    pub fn new(seed: u32, rules: Arc<TerrainRules>, surface: Surface) -> Self {
+        let random_temperature = Perlin::new().set_seed(seed);
+        TerrainTemperatureFactory {
+            random_temperature,
+            rules,
+            surface,
+        }
+    }
+
+    fn random_temperature(&self, nx: f64, ny: f64) -> f64 {
+        let variation = self.rules.temperature.variation;
+        if variation == 0. {
+            return 0.;
+        }
+        self.surface.sample(&self.random_temperature, nx, ny, 2.) * variation
+    }
+
+    pub fn create(
+        &self,
+        nx: f64,
+        ny: f64,
+        latitude: Latitude,
+        elevation: Elevation,
+    ) -> Temperature {
+        let rules = &self.rules.temperature;
+        let abs_latitude: f64 = latitude.abs().into();
+        // like on earth it cools slowly around the equator and fast towards the poles
+        let at_sea_level =
+            rules.pole + (rules.equator - rules.pole) * abs_latitude.to_radians().cos();
+        let above_sea_level =
+            (Into::<f64>::into(elevation) - self.rules.thresholds.ocean_elevation).max(0.);
+        Temperature::new(
+            at_sea_level - above_sea_level * rules.lapse_rate + self.random_temperature(nx, ny),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::saturating_from::SaturatingInto;
+
+    #[test]
+    fn test_temperature() {
+        let mut rules = TerrainRules::default();
+        rules.temperature.variation = 0.;
+        let (equator, pole, lapse_rate) = (
+            rules.temperature.equator,
+            rules.temperature.pole,
+            rules.temperature.lapse_rate,
+        );
+        let factory = TerrainTemperatureFactory::new(1, Arc::new(rules), Surface::Plane);
+        let temperature = |ny: f64, elevation: f64| -> f64 {
+            factory
+                .create(0., ny, ny.saturating_into(), elevation.saturating_into())
+                .into()
+        };
+        assert_eq!(temperature(0., 0.), equator);
+        assert!((temperature(1., 0.) - pole).abs() < 1e-9);
+        assert_eq!(temperature(-0.5, 0.), temperature(0.5, 0.));
+        assert!(temperature(0.5, 0.) < temperature(0.25, 0.));
+        // the ocean floor is as warm as the sea level
+        assert_eq!(temperature(0., 0.1), temperature(0., 0.));
+        assert_eq!(temperature(0., 0.6), equator - 0.5 * lapse_rate);
+
+        let varied = TerrainTemperatureFactory::new(1, Arc::default(), Surface::Plane);
+        let difference = Into::<f64>::into(varied.create(
+            0.3,
+            0.4,
+            0.4.saturating_into(),
+            0.3.saturating_into(),
+        )) - temperature(0.4, 0.3);
+        assert!(difference.abs() <= 3.);
+    }
+}
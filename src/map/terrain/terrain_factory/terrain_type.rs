@@ -1,8 +1,24 @@
-use crate::map::terrain::{Elevation, Latitude, Moisture};
-use crate::saturating_from::SaturatingInto;
+use crate::map::terrain::terrain_factory::terrain_temperature::Temperature;
+use crate::map::terrain::terrain_rules::TerrainRules;
+use crate::map::terrain::{Elevation, Moisture};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use strum_macros::{EnumCount, EnumIter, EnumVariantNames, IntoStaticStr};
 
-#[derive(PartialEq, Eq, Copy, Clone, EnumIter, EnumCount, IntoStaticStr, EnumVariantNames)]
+#[derive(
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Copy,
+    Clone,
+    EnumIter,
+    EnumCount,
+    IntoStaticStr,
+    EnumVariantNames,
+    Serialize,
+    Deserialize,
+)]
 pub enum TerrainType {
     Bare,
     Grassland,
@@ -87,168 +103,103 @@ impl TerrainType {
     }
 }
 
-pub struct TerrainConstants {
-    pub freshwater_moisture_threshold: Moisture,
-    pub hill_elevation_threshold: Elevation,
-    pub mountain_elevation_threshold: Elevation,
-    pub ocean_elevation_threshold: Elevation,
-    pub saltflat_elevation_threshold: Elevation,
-}
-
-// not individual constants since const_fn is unstable for initialization
-lazy_static! {
-    pub static ref TERRAIN_CONSTANTS: TerrainConstants = TerrainConstants {
-        freshwater_moisture_threshold: SaturatingInto::saturating_from(&0.87),
-        hill_elevation_threshold: SaturatingInto::saturating_from(&0.55),
-        mountain_elevation_threshold: SaturatingInto::saturating_from(&0.75),
-        ocean_elevation_threshold: SaturatingInto::saturating_from(&0.1),
-        saltflat_elevation_threshold: SaturatingInto::saturating_from(&0.12),
-    };
-}
-
 impl Default for TerrainType {
     fn default() -> Self {
         Self::Bare
     }
 }
 
-pub struct TerrainTypeFactory;
+pub struct TerrainTypeFactory {
+    rules: Arc<TerrainRules>,
+}
 
 impl TerrainTypeFactory {
-    pub fn new() -> Self {
-        TerrainTypeFactory {}
+    pub fn new(rules: Arc<TerrainRules>) -> Self {
+        TerrainTypeFactory { rules }
     }
 
     pub fn create(
         &self,
-        latitude: Latitude,
+        temperature: Temperature,
         elevation: Elevation,
         moisture: Moisture,
     ) -> TerrainType {
-        if elevation > TERRAIN_CONSTANTS.mountain_elevation_threshold {
-            if moisture < 0.1 {
+        let thresholds = &self.rules.thresholds;
+        let elevation: f64 = elevation.into();
+        let moisture: f64 = moisture.into();
+        if elevation > thresholds.mountain_elevation {
+            if moisture < thresholds.desert_moisture {
                 return TerrainType::DesertMountain;
             }
             return TerrainType::Mountain;
         }
-        let base_terrain_type = Self::base_terrain_type(latitude, elevation, moisture);
-        if elevation > TERRAIN_CONSTANTS.hill_elevation_threshold {
-            if moisture < 0.1 {
+        let base_terrain_type = self.base_terrain_type(temperature.into(), elevation, moisture);
+        if elevation > thresholds.hill_elevation {
+            if moisture < thresholds.desert_moisture {
                 return TerrainType::DesertHills;
             }
-            return match base_terrain_type {
-                TerrainType::TemperateDeciduousForest
-                | TerrainType::TemperateRainForest
-                | TerrainType::TropicalSeasonalForest
-                | TerrainType::TropicalRainForest => TerrainType::WoodedHills,
-                TerrainType::Taiga => TerrainType::TaigaHills,
-                TerrainType::Snow => TerrainType::SnowHills,
-                _ => TerrainType::Hills,
-            };
+            return self.rules.hill(&base_terrain_type);
         }
-        return base_terrain_type;
+        base_terrain_type
     }
 
-    fn base_terrain_type(
-        latitude: Latitude,
-        elevation: Elevation,
-        moisture: Moisture,
-    ) -> TerrainType {
-        let abs_latitude: f64 = Into::<f64>::into(latitude).abs();
-        if abs_latitude > 89.25 {
-            if elevation < 0.1 {
-                return TerrainType::Ice;
-            }
-            return TerrainType::Snow;
-        }
-        if elevation > 0.2 && moisture > TERRAIN_CONSTANTS.freshwater_moisture_threshold {
+    /// water by elevation and moisture, land by temperature and moisture, and high up by elevation
+    fn base_terrain_type(&self, temperature: f64, elevation: f64, moisture: f64) -> TerrainType {
+        let thresholds = &self.rules.thresholds;
+        let climate = match self.rules.climate(temperature) {
+            Some(climate) => climate,
+            None if elevation < thresholds.ocean_elevation => return self.rules.frozen.sea,
+            None => return self.rules.frozen.land,
+        };
+        if elevation > thresholds.freshwater_elevation && moisture > thresholds.freshwater_moisture
+        {
             return TerrainType::FreshWater;
         }
-        // arctic starts at 66.5
-        if abs_latitude > 87. {
-            if elevation < TERRAIN_CONSTANTS.ocean_elevation_threshold {
-                if moisture > 0.5 {
-                    return TerrainType::Ocean;
-                }
-                return TerrainType::Ice;
-            }
-            if moisture < 0.1 {
-                return TerrainType::Scorched;
-            }
-            if moisture < 0.2 {
-                return TerrainType::Bare;
-            }
-            if elevation < 0.7 {
-                if moisture < 0.7 {
-                    return TerrainType::Tundra;
-                }
-                return TerrainType::TundraMarsh;
+        if elevation < thresholds.ocean_elevation {
+            if climate.sea_ice && moisture <= thresholds.sea_ice_moisture {
+                return self.rules.frozen.sea;
             }
-            return TerrainType::Snow;
-        }
-
-        if elevation < TERRAIN_CONSTANTS.ocean_elevation_threshold {
             return TerrainType::Ocean;
         }
-        if elevation > 0.8 {
-            if moisture < 0.1 {
-                return TerrainType::Scorched;
-            }
-            if moisture < 0.2 {
-                return TerrainType::Bare;
-            }
-            return TerrainType::Snow;
-        }
-
-        if elevation < TERRAIN_CONSTANTS.saltflat_elevation_threshold && moisture < 0.2 {
+        if climate.salt_flats
+            && elevation < thresholds.saltflat_elevation
+            && moisture < thresholds.saltflat_moisture
+        {
             return TerrainType::SaltFlat;
         }
+        climate.biome(moisture, elevation > thresholds.alpine_elevation)
+    }
+}
 
-        if abs_latitude > 80. {
-            if moisture < 0.1 {
-                return TerrainType::Scorched;
-            }
-            if moisture < 0.2 {
-                return TerrainType::Bare;
-            }
-            if moisture < 0.85 {
-                return TerrainType::Taiga;
-            }
-            return TerrainType::Marsh;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::terrain::terrain_factory::terrain_temperature::TerrainTemperatureFactory;
+    use crate::map::terrain::Surface;
+    use crate::saturating_from::SaturatingInto;
 
-        if abs_latitude > 30. {
-            if elevation > 0.6 {
-                if moisture < 0.33 {
-                    return TerrainType::TemperateDesert;
-                }
-                if moisture < 0.66 {
-                    return TerrainType::Shrubland;
-                }
-                return TerrainType::Grassland;
-            }
+    #[test]
+    fn test_terrain_type() {
+        let mut rules = TerrainRules::default();
+        rules.temperature.variation = 0.;
+        let rules = Arc::new(rules);
+        let temperature_factory = TerrainTemperatureFactory::new(1, rules.clone(), Surface::Plane);
+        let type_factory = TerrainTypeFactory::new(rules);
+        let terrain_type = |ny: f64, elevation: f64, moisture: f64| -> TerrainType {
+            let elevation: Elevation = elevation.saturating_into();
+            let temperature = temperature_factory.create(0., ny, ny.saturating_into(), elevation);
+            type_factory.create(temperature, elevation, moisture.saturating_into())
+        };
 
-            if moisture < 0.1 {
-                return TerrainType::TemperateDesert;
-            }
-            if moisture < 0.70 {
-                return TerrainType::Grassland;
-            }
-            if moisture < 0.83 {
-                return TerrainType::TemperateDeciduousForest;
-            }
-            return TerrainType::TemperateRainForest;
-        }
-
-        if moisture < 0.1 {
-            return TerrainType::SubtropicalDesert;
-        }
-        if moisture < 0.5 {
-            return TerrainType::Grassland;
-        }
-        if moisture < 0.72 {
-            return TerrainType::TropicalSeasonalForest;
-        }
-        return TerrainType::TropicalRainForest;
+        // the same latitude and moisture, only higher up and so colder
+        assert!(terrain_type(0., 0.2, 0.6) == TerrainType::TropicalSeasonalForest);
+        assert!(terrain_type(0., 0.5, 0.6) == TerrainType::Shrubland);
+        assert!(terrain_type(0., 0.6, 0.6) == TerrainType::TaigaHills);
+        assert!(terrain_type(0.3, 0.2, 0.6) == TerrainType::Grassland);
+        assert!(terrain_type(0.3, 0.4, 0.6) == TerrainType::Taiga);
+        assert!(terrain_type(0.3, 0.6, 0.6) == TerrainType::Hills);
+        // the poles freeze, mountains stay mountains
+        assert!(terrain_type(1., 0.2, 0.6) == TerrainType::Snow);
+        assert!(terrain_type(0., 0.8, 0.6) == TerrainType::Mountain);
     }
 }
@@ -0,0 +1,532 @@
+use crate::good::{HarvestableGood, NaturalGood};
+use crate::map::terrain::TerrainType;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use strum_macros::AsRefStr;
+
+/// the rules every world had before they were configurable
+const BUILT_IN: &str = include_str!("terrain_rules.ron");
+
+#[derive(Debug, AsRefStr)]
+pub enum TerrainRulesError {
+    Io(io::Error),
+    Parse(ron::Error),
+    /// parsed, but e.g. thresholds out of order, the message tells which
+    Invalid(String),
+}
+
+impl fmt::Display for TerrainRulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TerrainRulesError::Io(error) => write!(f, "{}: {}", self.as_ref(), error),
+            TerrainRulesError::Parse(error) => write!(f, "{}: {}", self.as_ref(), error),
+            TerrainRulesError::Invalid(reason) => write!(f, "{}: {}", self.as_ref(), reason),
+        }
+    }
+}
+
+impl Error for TerrainRulesError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TerrainRulesError::Io(error) => Some(error),
+            TerrainRulesError::Parse(error) => Some(error),
+            TerrainRulesError::Invalid(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for TerrainRulesError {
+    fn from(error: io::Error) -> Self {
+        TerrainRulesError::Io(error)
+    }
+}
+
+impl From<ron::Error> for TerrainRulesError {
+    fn from(error: ron::Error) -> Self {
+        TerrainRulesError::Parse(error)
+    }
+}
+
+fn invalid<T>(reason: String) -> Result<T, TerrainRulesError> {
+    Err(TerrainRulesError::Invalid(reason))
+}
+
+fn one() -> f64 {
+    1.
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Thresholds {
+    pub ocean_elevation: f64,
+    pub saltflat_elevation: f64,
+    pub freshwater_elevation: f64,
+    pub alpine_elevation: f64,
+    pub hill_elevation: f64,
+    pub mountain_elevation: f64,
+    pub desert_moisture: f64,
+    pub saltflat_moisture: f64,
+    pub freshwater_moisture: f64,
+    pub sea_ice_moisture: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemperatureRules {
+    /// at sea level, in degrees celsius, in between it goes with the cosine of the latitude
+    pub equator: f64,
+    pub pole: f64,
+    /// how much colder it gets from the sea level up to an elevation of 1
+    pub lapse_rate: f64,
+    /// the most the noise warms or cools a coordinate, 0 for none
+    pub variation: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frozen {
+    pub land: TerrainType,
+    pub sea: TerrainType,
+}
+
+/// a temperature zone with its own biomes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Climate {
+    pub name: String,
+    /// in degrees celsius, the climate reaches from here up to the next warmer one
+    pub coldest: f64,
+    /// the biomes up to the moisture they get, from the driest up
+    pub biomes: Vec<(f64, TerrainType)>,
+    pub wettest: TerrainType,
+    /// the biomes above `Thresholds::alpine_elevation`, the same as below without them
+    #[serde(default)]
+    pub alpine: Option<Alpine>,
+    /// whether the sea freezes where it is dry
+    #[serde(default)]
+    pub sea_ice: bool,
+    #[serde(default = "salt_flats")]
+    pub salt_flats: bool,
+}
+
+fn salt_flats() -> bool {
+    true
+}
+
+/// the biomes of a climate high up, but still below the hills
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alpine {
+    pub biomes: Vec<(f64, TerrainType)>,
+    pub wettest: TerrainType,
+}
+
+fn biome(biomes: &[(f64, TerrainType)], wettest: TerrainType, moisture: f64) -> TerrainType {
+    biomes
+        .iter()
+        .find(|(up_to, _)| moisture < *up_to)
+        .map_or(wettest, |(_, terrain_type)| *terrain_type)
+}
+
+impl Climate {
+    pub fn biome(&self, moisture: f64, alpine: bool) -> TerrainType {
+        match &self.alpine {
+            Some(high_up) if alpine => biome(&high_up.biomes, high_up.wettest, moisture),
+            _ => biome(&self.biomes, self.wettest, moisture),
+        }
+    }
+}
+
+/// which terrain a yield rule applies to
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TerrainClass {
+    Type(TerrainType),
+    Ground,
+    FlatGround,
+    Hill,
+    HillWithSnow,
+    Mountain,
+    Water,
+    Ocean,
+    Wooded,
+    Rainforest,
+}
+
+impl TerrainClass {
+    pub fn contains(&self, terrain_type: &TerrainType) -> bool {
+        match self {
+            TerrainClass::Type(expected) => expected == terrain_type,
+            TerrainClass::Ground => terrain_type.is_ground(),
+            TerrainClass::FlatGround => terrain_type.is_flat_ground(),
+            TerrainClass::Hill => terrain_type.is_hill(),
+            TerrainClass::HillWithSnow => terrain_type.is_hill_with_snow(),
+            TerrainClass::Mountain => terrain_type.is_mountain(),
+            TerrainClass::Water => terrain_type.is_water(),
+            TerrainClass::Ocean => terrain_type.is_ocean(),
+            TerrainClass::Wooded => terrain_type.is_wooded(),
+            TerrainClass::Rainforest => terrain_type.is_rainforest(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MoistureEffect {
+    Ignored,
+    /// moisture to the power of, e.g. -1 for goods that like it dry
+    Power(f64),
+    /// from nothing when dry to everything at the fresh water moisture, biased by the power
+    Saturating(f64),
+}
+
+impl Default for MoistureEffect {
+    fn default() -> Self {
+        MoistureEffect::Ignored
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoiseRule {
+    pub frequency: f64,
+    pub harmonics: usize,
+    #[serde(default = "one")]
+    pub exponent: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct YieldRule {
+    /// any of these, empty for all terrain
+    pub terrain: Vec<TerrainClass>,
+    /// the latitude bounds are exclusive and in degrees from the equator
+    #[serde(default)]
+    pub min_latitude: Option<f64>,
+    #[serde(default)]
+    pub max_latitude: Option<f64>,
+    /// in degrees from the prime meridian, east or west
+    #[serde(default)]
+    pub min_longitude: Option<f64>,
+    /// only along rivers, scaled by their strength
+    #[serde(default)]
+    pub river: bool,
+    #[serde(default = "one")]
+    pub productivity: f64,
+    #[serde(default)]
+    pub moisture: MoistureEffect,
+    #[serde(default)]
+    pub noise: Option<NoiseRule>,
+}
+
+impl YieldRule {
+    /// `abs_latitude` and `abs_longitude` in degrees
+    pub fn applies(
+        &self,
+        terrain_type: &TerrainType,
+        abs_latitude: f64,
+        abs_longitude: f64,
+        river: f64,
+    ) -> bool {
+        (self.terrain.is_empty() || self.terrain.iter().any(|c| c.contains(terrain_type)))
+            && self.min_latitude.map_or(true, |min| abs_latitude > min)
+            && self.max_latitude.map_or(true, |max| abs_latitude < max)
+            && self.min_longitude.map_or(true, |min| abs_longitude > min)
+            && (!self.river || river > 0.)
+    }
+}
+
+/// Everything the terrain generation can be tuned with: the thresholds between elevations and
+/// moistures, the climates and their biomes, and the yields of the natural and harvestable goods.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TerrainRules {
+    pub thresholds: Thresholds,
+    pub temperature: TemperatureRules,
+    pub frozen: Frozen,
+    pub climates: Vec<Climate>,
+    pub hills: HashMap<TerrainType, TerrainType>,
+    pub natural_yields: HashMap<NaturalGood, Vec<YieldRule>>,
+    pub harvestable_yields: HashMap<HarvestableGood, Vec<YieldRule>>,
+}
+
+impl Default for TerrainRules {
+    fn default() -> Self {
+        TerrainRules::from_ron(BUILT_IN).expect("the built-in terrain rules should be valid")
+    }
+}
+
+impl TerrainRules {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TerrainRulesError> {
+        TerrainRules::from_ron(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_ron(ron: &str) -> Result<Self, TerrainRulesError> {
+        let rules: TerrainRules = ron::de::from_str(ron)?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    pub fn to_ron(&self) -> Result<String, TerrainRulesError> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    /// the climate of `temperature`, `None` if it is frozen
+    pub fn climate(&self, temperature: f64) -> Option<&Climate> {
+        self.climates
+            .iter()
+            .find(|climate| temperature >= climate.coldest)
+    }
+
+    /// the hills version of a biome
+    pub fn hill(&self, biome: &TerrainType) -> TerrainType {
+        *self.hills.get(biome).unwrap_or(&TerrainType::Hills)
+    }
+
+    fn validate(&self) -> Result<(), TerrainRulesError> {
+        let thresholds = &self.thresholds;
+        let fractions = [
+            ("ocean_elevation", thresholds.ocean_elevation),
+            ("saltflat_elevation", thresholds.saltflat_elevation),
+            ("freshwater_elevation", thresholds.freshwater_elevation),
+            ("alpine_elevation", thresholds.alpine_elevation),
+            ("hill_elevation", thresholds.hill_elevation),
+            ("mountain_elevation", thresholds.mountain_elevation),
+            ("desert_moisture", thresholds.desert_moisture),
+            ("saltflat_moisture", thresholds.saltflat_moisture),
+            ("freshwater_moisture", thresholds.freshwater_moisture),
+            ("sea_ice_moisture", thresholds.sea_ice_moisture),
+        ];
+        for (name, value) in fractions.iter() {
+            if !(0. ..=1.).contains(value) {
+                return invalid(format!(
+                    "thresholds.{} is {}, it has to be between 0 and 1",
+                    name, value
+                ));
+            }
+        }
+        // freshwater_moisture is divided by its distance to 1 for the fresh water yield
+        if thresholds.freshwater_moisture >= 1. {
+            return invalid("thresholds.freshwater_moisture has to be below 1".to_string());
+        }
+        let elevations = [
+            ("ocean_elevation", thresholds.ocean_elevation),
+            ("alpine_elevation", thresholds.alpine_elevation),
+            ("hill_elevation", thresholds.hill_elevation),
+            ("mountain_elevation", thresholds.mountain_elevation),
+        ];
+        for pair in elevations.windows(2) {
+            if pair[0].1 >= pair[1].1 {
+                return invalid(format!(
+                    "thresholds.{} ({}) has to be below thresholds.{} ({})",
+                    pair[0].0, pair[0].1, pair[1].0, pair[1].1
+                ));
+            }
+        }
+
+        let temperature = &self.temperature;
+        let temperatures = [
+            ("equator", temperature.equator),
+            ("pole", temperature.pole),
+            ("lapse_rate", temperature.lapse_rate),
+            ("variation", temperature.variation),
+        ];
+        for (name, value) in temperatures.iter() {
+            if !value.is_finite() {
+                return invalid(format!(
+                    "temperature.{} is {}, it has to be a number",
+                    name, value
+                ));
+            }
+        }
+        if temperature.equator < temperature.pole {
+            return invalid(format!(
+                "temperature.equator ({}) can't be colder than temperature.pole ({})",
+                temperature.equator, temperature.pole
+            ));
+        }
+        if temperature.lapse_rate < 0. || temperature.variation < 0. {
+            return invalid(
+                "temperature.lapse_rate and temperature.variation can't be negative".to_string(),
+            );
+        }
+
+        if self.climates.is_empty() {
+            return invalid("there has to be at least one climate".to_string());
+        }
+        for climate in self.climates.iter() {
+            if !climate.coldest.is_finite() {
+                return invalid(format!(
+                    "the coldest temperature of climate {} is {}, it has to be a number",
+                    climate.name, climate.coldest
+                ));
+            }
+        }
+        for pair in self.climates.windows(2) {
+            if pair[0].coldest <= pair[1].coldest {
+                return invalid(format!(
+                    "climate {} has to be warmer than climate {}, they go from the warmest down",
+                    pair[0].name, pair[1].name
+                ));
+            }
+        }
+        for climate in self.climates.iter() {
+            let alpine = climate.alpine.iter();
+            let biomes = std::iter::once((&climate.biomes, &climate.wettest))
+                .chain(alpine.map(|alpine| (&alpine.biomes, &alpine.wettest)));
+            for (biomes, wettest) in biomes {
+                if let Some((moisture, _)) = biomes.iter().find(|(up_to, _)| !up_to.is_finite()) {
+                    return invalid(format!(
+                        "climate {} has a biome up to {}, it has to be a number",
+                        climate.name, moisture
+                    ));
+                }
+                for pair in biomes.windows(2) {
+                    if pair[0].0 >= pair[1].0 {
+                        return invalid(format!(
+                            "the biomes of climate {} have to go from the driest up, {} isn't below {}",
+                            climate.name, pair[0].0, pair[1].0
+                        ));
+                    }
+                }
+                let biomes = biomes.iter().map(|(_, biome)| biome);
+                for biome in biomes.chain(std::iter::once(wettest)) {
+                    if biome.is_water() || biome.is_hill() || biome.is_mountain() {
+                        return invalid(format!(
+                            "climate {} has {}, biomes can't be water, hills or mountains",
+                            climate.name,
+                            Into::<&str>::into(biome)
+                        ));
+                    }
+                }
+            }
+        }
+        for (biome, hill) in self.hills.iter() {
+            if !hill.is_hill() {
+                return invalid(format!(
+                    "hills of {} are {}, which isn't a hill",
+                    Into::<&str>::into(biome),
+                    Into::<&str>::into(hill)
+                ));
+            }
+        }
+
+        let natural = self
+            .natural_yields
+            .iter()
+            .map(|(good, rules)| (good.as_ref(), rules));
+        let harvestable = self
+            .harvestable_yields
+            .iter()
+            .map(|(good, rules)| (good.as_ref(), rules));
+        for (good, rules) in natural.chain(harvestable) {
+            for (idx, rule) in rules.iter().enumerate() {
+                Self::validate_yield_rule(rule)
+                    .map_err(|reason| format!("yield rule {} of {}: {}", idx + 1, good, reason))
+                    .or_else(invalid)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_yield_rule(rule: &YieldRule) -> Result<(), String> {
+        if !rule.productivity.is_finite() || rule.productivity < 0. {
+            return Err(format!(
+                "productivity is {}, it can't be negative",
+                rule.productivity
+            ));
+        }
+        for latitude in rule.min_latitude.iter().chain(rule.max_latitude.iter()) {
+            if !(0. ..=90.).contains(latitude) {
+                return Err(format!(
+                    "latitude {} has to be between 0 and 90 degrees",
+                    latitude
+                ));
+            }
+        }
+        if let (Some(min), Some(max)) = (rule.min_latitude, rule.max_latitude) {
+            if min >= max {
+                return Err(format!(
+                    "min_latitude ({}) has to be below max_latitude ({})",
+                    min, max
+                ));
+            }
+        }
+        if let Some(longitude) = rule.min_longitude {
+            if !(0. ..=180.).contains(&longitude) {
+                return Err(format!(
+                    "min_longitude {} has to be between 0 and 180 degrees",
+                    longitude
+                ));
+            }
+        }
+        if let Some(noise) = rule.noise {
+            if !noise.frequency.is_finite() || noise.frequency <= 0. || noise.harmonics == 0 {
+                return Err("noise needs a positive frequency and harmonics".to_string());
+            }
+            if !noise.exponent.is_finite() {
+                return Err(format!(
+                    "noise exponent is {}, it has to be a number",
+                    noise.exponent
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terrain_rules() {
+        let rules = TerrainRules::default();
+        assert_eq!(rules.climates.len(), 4);
+        assert!(rules.climate(25.).unwrap().name == "tropical");
+        assert!(rules.climate(-10.).unwrap().name == "polar");
+        assert!(rules.climate(-20.).is_none());
+        let temperate = rules.climate(10.).unwrap();
+        assert!(temperate.biome(0.75, false) == TerrainType::TemperateDeciduousForest);
+        assert!(temperate.biome(0.95, false) == TerrainType::TemperateRainForest);
+        assert!(temperate.biome(0.5, true) == TerrainType::Shrubland);
+        // without alpine biomes it is the same high up
+        let boreal = rules.climate(0.).unwrap();
+        assert!(boreal.biome(0.5, true) == boreal.biome(0.5, false));
+        assert!(rules.hill(&TerrainType::Taiga) == TerrainType::TaigaHills);
+        assert!(rules.hill(&TerrainType::Grassland) == TerrainType::Hills);
+
+        let ron = rules.to_ron().unwrap();
+        assert_eq!(TerrainRules::from_ron(&ron).unwrap(), rules);
+
+        let swapped = BUILT_IN.replace("mountain_elevation: 0.75", "mountain_elevation: 0.5");
+        let error = TerrainRules::from_ron(&swapped).unwrap_err().to_string();
+        assert_eq!(
+            error,
+            "Invalid: thresholds.hill_elevation (0.55) has to be below \
+             thresholds.mountain_elevation (0.5)"
+        );
+        let no_harmonics = BUILT_IN.replace(
+            "(terrain: [Mountain], noise: (frequency: 6.0, harmonics: 3))",
+            "(terrain: [Mountain], noise: (frequency: 6.0, harmonics: 0))",
+        );
+        let error = TerrainRules::from_ron(&no_harmonics)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Invalid: yield rule 1 of GemStoneRepo: noise needs a positive frequency and harmonics"
+        );
+        let not_a_number = BUILT_IN.replace("lapse_rate: 45.0", "lapse_rate: NaN");
+        let error = TerrainRules::from_ron(&not_a_number)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Invalid: temperature.lapse_rate is NaN, it has to be a number"
+        );
+        let unknown_biome = BUILT_IN.replace("wettest: Marsh", "wettest: Swamp");
+        assert!(matches!(
+            TerrainRules::from_ron(&unknown_biome),
+            Err(TerrainRulesError::Parse(_))
+        ));
+    }
+}
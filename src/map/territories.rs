@@ -2,7 +2,8 @@ use crate::coordinate::range::Range;
 use crate::coordinate::Coordinate;
 pub use crate::map::buildings::territories_state::{TerritoriesState, TerritoriesStateRw};
 use crate::map::minimap::{FillByCoordinate, GetByCoordinate, Minimap, SetByCoordinate, WithGrid};
-use crate::observable::{Observable, Observers};
+use crate::observable::{Batch, Dispatch, Observable, Observers};
+use serde::{Deserialize, Serialize};
 
 use self::territories_storage::TerritoriesStorage;
 pub use self::territories_storage::TerritoryID;
@@ -17,17 +18,65 @@ pub struct Territories {
     columns: usize,
     joiners: Observers<TerritoryJoined>,
     leavers: Observers<TerritoryLeft>,
+    batch: Batch<TerritoriesChanged>,
+    changes: Observers<TerritoriesChanged>,
 }
 
 impl Territories {
-    pub fn new(rows: usize, columns: usize) -> Self {
+    pub fn new(rows: usize, columns: usize, dispatch: Dispatch) -> Self {
         Territories {
             territories: Default::default(),
             next_territory_id: Default::default(),
             rows,
             columns,
-            joiners: Default::default(),
-            leavers: Default::default(),
+            joiners: Observers::with_dispatch(dispatch),
+            leavers: Observers::with_dispatch(dispatch),
+            batch: Default::default(),
+            changes: Observers::with_dispatch(dispatch),
+        }
+    }
+
+    /// if enabled, changes are only delivered as `TerritoriesChanged`, one per batch
+    pub fn set_batching(&mut self, enabled: bool) {
+        self.batch.set_enabled(enabled);
+    }
+
+    pub fn begin_batch(&mut self) {
+        self.batch.begin();
+    }
+
+    pub fn end_batch(&mut self) {
+        if self.batch.end() {
+            self.flush_batch();
+        }
+    }
+
+    fn flush_batch(&mut self) {
+        let changes = self.batch.take();
+        if !changes.is_empty() {
+            self.notify_all(changes);
+        }
+    }
+
+    fn joined(&mut self, joined: TerritoryJoined) {
+        if self.batch.is_enabled() {
+            self.batch.pending_mut().joined.push(joined);
+            if !self.batch.is_open() {
+                self.flush_batch();
+            }
+        } else {
+            self.notify_all(joined);
+        }
+    }
+
+    fn left(&mut self, left: TerritoryLeft) {
+        if self.batch.is_enabled() {
+            self.batch.pending_mut().left.push(left);
+            if !self.batch.is_open() {
+                self.flush_batch();
+            }
+        } else {
+            self.notify_all(left);
         }
     }
 
@@ -38,8 +87,10 @@ impl Territories {
         territory_id
     }
 
+    /// the coordinates of `range` on the grid that don't belong to a territory yet
     pub fn extend(&mut self, territory_id: &TerritoryID, range: Range) {
-        let filtered_range = range
+        let filtered_range = self
+            .clip(&range)
             .into_iter()
             .filter(|coordinate| {
                 let maybe_territory_id: Option<TerritoryID> = self.get(coordinate);
@@ -84,19 +135,19 @@ impl SetByCoordinate<Option<TerritoryID>> for Territories {
             Some(territory_id) => {
                 let maybe_old_territory_id = self.territories.insert(coordinate, territory_id);
                 if let Some(old_territory_id) = maybe_old_territory_id {
-                    self.notify_all(TerritoryLeft {
+                    self.left(TerritoryLeft {
                         coordinate,
                         territory_id: old_territory_id,
                     });
                 }
-                self.notify_all(TerritoryJoined {
+                self.joined(TerritoryJoined {
                     coordinate,
                     territory_id,
                 });
             }
             None => {
                 if let Some(old_territory_id) = self.territories.remove(&coordinate) {
-                    self.notify_all(TerritoryLeft {
+                    self.left(TerritoryLeft {
                         coordinate,
                         territory_id: old_territory_id,
                     });
@@ -110,7 +161,7 @@ impl FillByCoordinate<Option<TerritoryID>> for Territories {}
 
 impl Minimap<Option<TerritoryID>> for Territories {}
 
-#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TerritoryJoined {
     pub coordinate: Coordinate,
     pub territory_id: TerritoryID,
@@ -122,7 +173,7 @@ impl Observable<TerritoryJoined> for Territories {
     }
 }
 
-#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TerritoryLeft {
     pub coordinate: Coordinate,
     pub territory_id: TerritoryID,
@@ -133,3 +184,94 @@ impl Observable<TerritoryLeft> for Territories {
         &self.leavers
     }
 }
+
+/// all territory changes of one batch
+#[derive(Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerritoriesChanged {
+    pub joined: Vec<TerritoryJoined>,
+    pub left: Vec<TerritoryLeft>,
+}
+
+impl TerritoriesChanged {
+    pub fn is_empty(&self) -> bool {
+        self.joined.is_empty() && self.left.is_empty()
+    }
+
+    pub fn coordinates(&self) -> Vec<Coordinate> {
+        self.joined
+            .iter()
+            .map(|joined| joined.coordinate)
+            .chain(self.left.iter().map(|left| left.coordinate))
+            .collect()
+    }
+}
+
+impl Observable<TerritoriesChanged> for Territories {
+    fn observers(&self) -> &Observers<TerritoriesChanged> {
+        &self.changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinate::range::RangeFrom;
+    use crate::coordinate::Offset;
+    use crate::observable::Observer;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct ChangesRecorder {
+        joined: Mutex<usize>,
+        batches: Mutex<Vec<TerritoriesChanged>>,
+    }
+
+    impl Observer<TerritoryJoined> for ChangesRecorder {
+        fn notify(&self, _event: &TerritoryJoined) {
+            *self.joined.lock().unwrap() += 1;
+        }
+    }
+
+    impl Observer<TerritoriesChanged> for ChangesRecorder {
+        fn notify(&self, event: &TerritoriesChanged) {
+            self.batches.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn test_batched_changes() {
+        let mut territories = Territories::new(20, 20, Dispatch::Synchronous);
+        territories.set_batching(true);
+        let recorder = Arc::new(ChangesRecorder::default());
+        let _joined = Observable::<TerritoryJoined>::observers(&territories).register(&recorder);
+        let _changes =
+            Observable::<TerritoriesChanged>::observers(&territories).register(&recorder);
+
+        territories.begin_batch();
+        let territory_id = territories.create(Coordinate::default().circle(1));
+        territories.begin_batch();
+        territories.extend(&territory_id, Coordinate::default().circle(2));
+        territories.end_batch();
+        assert!(recorder.batches.lock().unwrap().is_empty());
+        territories.end_batch();
+
+        let batches = recorder.batches.lock().unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].joined.len(), 19);
+        assert_eq!(batches[0].coordinates().len(), 19);
+        assert_eq!(*recorder.joined.lock().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut territories = Territories::new(4, 4, Dispatch::Synchronous);
+        let corner = Coordinate::from(Offset::new(1, 1));
+        let territory_id = territories.create(corner.circle(2));
+        let territory = territories.get_territory(&territory_id).unwrap();
+        assert!(territory.contains(&corner));
+        assert!(territory.len() < corner.circle(2).len());
+        assert!(territory.iter().all(|c| territories.contains(&c)));
+        let outside: Option<TerritoryID> = territories.get(&Offset::new(3, 1).into());
+        assert!(outside.is_none());
+    }
+}
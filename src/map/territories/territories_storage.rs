@@ -1,9 +1,23 @@
 use crate::coordinate::range::Range;
 use crate::coordinate::Coordinate;
 use derive_more::{AddAssign, Constructor, From, Into};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Default, Hash, Clone, Copy, PartialEq, Eq, Constructor, From, Into, AddAssign)]
+#[derive(
+    Default,
+    Hash,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Constructor,
+    From,
+    Into,
+    AddAssign,
+    Serialize,
+    Deserialize,
+)]
 pub struct TerritoryID(usize);
 
 #[derive(Default)]
@@ -23,7 +37,7 @@ impl TerritoriesStorage {
         territory_id: TerritoryID,
     ) -> Option<TerritoryID> {
         if !self.by_territory_id.contains_key(&territory_id) {
-            self.by_territory_id.insert(territory_id, Range::new());
+            self.by_territory_id.insert(territory_id, Range::default());
         }
         self.by_territory_id
             .get_mut(&territory_id)
@@ -57,7 +71,7 @@ impl TerritoriesStorage {
         let maybe_range = self.by_territory_id.remove(territory_id);
         if let Some(range) = &maybe_range {
             range.iter().for_each(|coordinate| {
-                self.by_coordinate.remove(coordinate);
+                self.by_coordinate.remove(&coordinate);
             });
         }
         maybe_range
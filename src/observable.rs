@@ -1,11 +1,18 @@
-use crossbeam::channel::{bounded, Receiver, Sender};
+use crossbeam::channel::{bounded, Receiver, SendTimeoutError, Sender, TrySendError};
 use derive_more::{From, Into};
+use log::warn;
 use rayon::prelude::*;
-use std::collections::HashSet;
-use std::hash::{Hash, Hasher};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock, Weak};
 use std::thread;
+use std::time::Duration;
+use strum_macros::AsRefStr;
+
+pub mod event_bus;
 
 pub trait Observer<E>: Send + Sync {
     fn notify(&self, event: &E);
@@ -14,42 +21,209 @@ pub trait Observer<E>: Send + Sync {
 /// weak pointer so it will deregister itself automatically when dropped
 type WeakObserver<E> = Weak<dyn Observer<E>>;
 
-#[derive(PartialEq, Eq, Hash, Copy, Clone, From, Into)]
+/// decides which events an observer is notified about
+pub type Filter<E> = Arc<dyn Fn(&E) -> bool + Send + Sync>;
+
+struct Registration<E> {
+    observer: WeakObserver<E>,
+    filter: Option<Filter<E>>,
+}
+
+impl<E> Clone for Registration<E> {
+    fn clone(&self) -> Self {
+        Registration {
+            observer: Weak::clone(&self.observer),
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+impl<E> Registration<E> {
+    /// false if the observer has since been freed
+    fn notify(&self, event: &E) -> bool {
+        match self.observer.upgrade() {
+            Some(observer) => {
+                if self.filter.as_ref().map_or(true, |filter| filter(event)) {
+                    observer.notify(event);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, From, Into)]
 struct ObserverId(usize);
 
-pub struct ObserverRegistration<E> {
-    weak: WeakObserver<E>,
+/// how events are delivered to the registered observers
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Dispatch {
+    /// queue the event and notify the observers in parallel on a consumer thread
+    Threaded,
+    /// notify the observers inline in registration order, `notify_all` returns when all are done
+    Synchronous,
+}
+
+impl Default for Dispatch {
+    fn default() -> Self {
+        Dispatch::Threaded
+    }
+}
+
+/// ordered by id, i.e. registration order
+type ObserversStore<E> = BTreeMap<ObserverId, Registration<E>>;
+
+trait Deregister: Send + Sync {
+    fn deregister(&self, id: &ObserverId) -> bool;
+}
+
+impl<E> Deregister for RwLock<ObserversStore<E>> {
+    fn deregister(&self, id: &ObserverId) -> bool {
+        self.write().unwrap().remove(id).is_some()
+    }
+}
+
+/// Deregisters the observer when dropped.
+#[must_use = "the observer is deregistered again when the subscription is dropped"]
+pub struct Subscription {
+    store: Weak<dyn Deregister>,
     id: ObserverId,
 }
 
-impl<E> Clone for ObserverRegistration<E> {
-    fn clone(&self) -> Self {
-        ObserverRegistration {
-            weak: Weak::clone(&self.weak),
-            id: self.id,
+impl Subscription {
+    /// explicitly deregister, returns false if the observer was already gone
+    pub fn unsubscribe(self) -> bool {
+        // dropping self deregisters again, which is a no-op by then
+        self.deregister()
+    }
+
+    fn deregister(&self) -> bool {
+        self.store
+            .upgrade()
+            .map_or(false, |store| store.deregister(&self.id))
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.deregister();
+    }
+}
+
+/// An observer together with its subscriptions, dropping it releases the observer and deregisters
+/// it everywhere.
+pub struct Subscribed<O> {
+    observer: Arc<O>,
+    subscriptions: Vec<Subscription>,
+}
+
+impl<O: 'static> Subscribed<O> {
+    pub fn new(observer: O) -> Self {
+        Subscribed {
+            observer: Arc::new(observer),
+            subscriptions: vec![],
         }
     }
+
+    pub fn subscribe<E>(mut self, observers: &Observers<E>) -> Self
+    where
+        E: 'static + Send + Sync,
+        O: Observer<E>,
+    {
+        let subscription = observers.register(&self.observer);
+        self.subscriptions.push(subscription);
+        self
+    }
+
+    pub fn subscribe_filtered<E>(
+        mut self,
+        observers: &Observers<E>,
+        filter: impl Fn(&E) -> bool + Send + Sync + 'static,
+    ) -> Self
+    where
+        E: 'static + Send + Sync,
+        O: Observer<E>,
+    {
+        let subscription = observers.register_filtered(&self.observer, filter);
+        self.subscriptions.push(subscription);
+        self
+    }
+
+    pub fn observer(&self) -> &Arc<O> {
+        &self.observer
+    }
 }
 
-impl<E> Hash for ObserverRegistration<E> {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.id.hash(state);
+impl<O> Deref for Subscribed<O> {
+    type Target = O;
+
+    fn deref(&self) -> &Self::Target {
+        &self.observer
     }
 }
 
-impl<E> Eq for ObserverRegistration<E> {}
+/// what happens to a new event when the queue of a threaded `Observers` is full
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Backpressure {
+    /// wait until the consumer has made room
+    Block,
+    /// discard the oldest queued event to make room
+    DropOldest,
+    /// discard the new event
+    DropNewest,
+    /// discard whatever is still pending whenever a new event is queued, for observers that only
+    /// care about the latest state
+    Coalesce,
+}
 
-impl<E> PartialEq for ObserverRegistration<E> {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+impl Default for Backpressure {
+    fn default() -> Self {
+        Backpressure::Block
     }
 }
 
-type ObserversStore<E> = HashSet<ObserverRegistration<E>>;
+#[derive(Debug, Copy, Clone, PartialEq, Eq, AsRefStr)]
+pub enum NotifyError {
+    /// the consumer thread is gone, e.g. because an observer panicked
+    Disconnected,
+}
 
-pub struct Observers<E> {
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_ref())
+    }
+}
+
+impl Error for NotifyError {}
+
+pub const DEFAULT_CAPACITY: usize = 100;
+
+/// how long a blocked sender waits before checking that the consumer is still alive
+const BLOCK_TIMEOUT: Duration = Duration::from_millis(100);
+
+struct EventQueue<E> {
     tx: Sender<E>,
+    // kept to be able to drop queued events
+    rx: Receiver<E>,
+    backpressure: Backpressure,
+    consumer_alive: Arc<AtomicBool>,
+}
+
+/// flags the consumer as gone once its thread ends, even if it unwinds
+struct ConsumerGuard(Arc<AtomicBool>);
+
+impl Drop for ConsumerGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+pub struct Observers<E> {
+    // only set for threaded dispatch
+    queue: Option<EventQueue<E>>,
     observers: Arc<RwLock<ObserversStore<E>>>,
+    dropped: AtomicUsize,
 }
 
 impl<E: 'static + Send + Sync> Default for Observers<E> {
@@ -62,80 +236,400 @@ static OBSERVER_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 impl<E: 'static + Send + Sync> Observers<E> {
     pub fn new() -> Self {
-        Self::with_capacity(100)
+        Self::with_capacity(DEFAULT_CAPACITY)
     }
 
     pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_backpressure(capacity, Backpressure::default())
+    }
+
+    pub fn with_backpressure(capacity: usize, backpressure: Backpressure) -> Self {
         let (tx, rx) = bounded(capacity);
+        let consumer_alive = Arc::new(AtomicBool::new(true));
         let observer = Observers {
-            tx,
+            queue: Some(EventQueue {
+                tx,
+                rx: rx.clone(),
+                backpressure,
+                consumer_alive: consumer_alive.clone(),
+            }),
             observers: Default::default(),
+            dropped: AtomicUsize::new(0),
         };
-        observer.consume_event(rx);
+        observer.consume_event(rx, ConsumerGuard(consumer_alive));
         observer
     }
 
-    // register/derigster doesn't need locking back off since there are no cycles
-    pub fn register<SO>(&self, observer: &Arc<SO>) -> ObserverRegistration<E>
+    pub fn synchronous() -> Self {
+        Observers {
+            queue: None,
+            observers: Default::default(),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn with_dispatch(dispatch: Dispatch) -> Self {
+        match dispatch {
+            Dispatch::Threaded => Self::new(),
+            Dispatch::Synchronous => Self::synchronous(),
+        }
+    }
+
+    pub fn dispatch(&self) -> Dispatch {
+        if self.queue.is_some() {
+            Dispatch::Threaded
+        } else {
+            Dispatch::Synchronous
+        }
+    }
+
+    pub fn backpressure(&self) -> Option<Backpressure> {
+        self.queue.as_ref().map(|queue| queue.backpressure)
+    }
+
+    /// number of events discarded by the backpressure policy so far
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.observers.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// the observer stays registered as long as both the observer and the subscription are alive
+    pub fn register<SO>(&self, observer: &Arc<SO>) -> Subscription
+    where
+        SO: 'static + Observer<E>,
+    {
+        self.insert(observer, None)
+    }
+
+    /// like `register`, but the observer is only notified about events `filter` accepts
+    pub fn register_filtered<SO>(
+        &self,
+        observer: &Arc<SO>,
+        filter: impl Fn(&E) -> bool + Send + Sync + 'static,
+    ) -> Subscription
+    where
+        SO: 'static + Observer<E>,
+    {
+        self.insert(observer, Some(Arc::new(filter)))
+    }
+
+    fn insert<SO>(&self, observer: &Arc<SO>, filter: Option<Filter<E>>) -> Subscription
     where
         SO: 'static + Observer<E>,
     {
-        let mut observers = self.observers.write().unwrap();
         let id: ObserverId = OBSERVER_COUNTER.fetch_add(1, Ordering::Relaxed).into();
-        let next_observer = ObserverRegistration {
-            weak: Arc::downgrade(observer) as WeakObserver<E>,
+        self.observers.write().unwrap().insert(
             id,
+            Registration {
+                observer: Arc::downgrade(observer) as WeakObserver<E>,
+                filter,
+            },
+        );
+        let store: Weak<dyn Deregister> = Arc::downgrade(&self.observers) as Weak<dyn Deregister>;
+        Subscription { store, id }
+    }
+
+    pub(crate) fn queue_event(&self, event: E) {
+        if let Err(error) = self.try_queue_event(event) {
+            warn!("event could not be delivered: {}", error);
+        }
+    }
+
+    pub(crate) fn try_queue_event(&self, event: E) -> Result<(), NotifyError> {
+        let queue = match &self.queue {
+            Some(queue) => queue,
+            None => {
+                Self::notify_inline(&self.observers, &event);
+                return Ok(());
+            }
         };
-        observers.insert(next_observer.clone());
-        next_observer
+        let mut event = event;
+        loop {
+            if !queue.consumer_alive.load(Ordering::Acquire) {
+                return Err(NotifyError::Disconnected);
+            }
+            if queue.backpressure == Backpressure::Coalesce {
+                let drained = queue.rx.try_iter().count();
+                self.dropped.fetch_add(drained, Ordering::Relaxed);
+            }
+            let full_event = match queue.backpressure {
+                Backpressure::Block => match queue.tx.send_timeout(event, BLOCK_TIMEOUT) {
+                    Ok(()) => return Ok(()),
+                    Err(SendTimeoutError::Timeout(event)) => event,
+                    Err(SendTimeoutError::Disconnected(_)) => {
+                        return Err(NotifyError::Disconnected)
+                    }
+                },
+                _ => match queue.tx.try_send(event) {
+                    Ok(()) => return Ok(()),
+                    Err(TrySendError::Full(event)) => event,
+                    Err(TrySendError::Disconnected(_)) => return Err(NotifyError::Disconnected),
+                },
+            };
+            event = full_event;
+            match queue.backpressure {
+                // blocking waits again, coalescing drains again as another event got in first
+                Backpressure::Block | Backpressure::Coalesce => {}
+                Backpressure::DropNewest => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+                Backpressure::DropOldest => {
+                    if queue.rx.try_recv().is_ok() {
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }
+        }
     }
 
-    pub fn deregister(&self, registration: &ObserverRegistration<E>) -> bool {
-        self.observers.write().unwrap().remove(registration)
+    /// snapshot of the registered observers, taken so no lock is held while they are notified
+    fn snapshot(observers: &RwLock<ObserversStore<E>>) -> Vec<(ObserverId, Registration<E>)> {
+        observers
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(id, registration)| (*id, registration.clone()))
+            .collect()
     }
 
-    fn queue_event(&self, event: E) {
-        self.tx.send(event).unwrap();
+    fn prune(observers: &RwLock<ObserversStore<E>>, dead: Vec<ObserverId>) {
+        if dead.is_empty() {
+            return;
+        }
+        let mut observers = observers.write().unwrap();
+        for id in dead.iter() {
+            observers.remove(id);
+        }
     }
 
-    fn consume_event(&self, rx: Receiver<E>) {
-        let weak_observers = Arc::downgrade(&self.observers);
-        thread::spawn(move || loop {
-            let maybe_event = rx.recv();
-            if maybe_event.is_err() {
-                // we have become disconnected
-                return;
+    fn notify_inline(observers: &RwLock<ObserversStore<E>>, event: &E) {
+        let mut dead = vec![];
+        for (id, registration) in Self::snapshot(observers) {
+            if !registration.notify(event) {
+                dead.push(id);
             }
-            let event = maybe_event.unwrap();
-            let maybe_observers = weak_observers.upgrade();
-            if maybe_observers.is_none() {
-                // doesn't really happen since we'd be disconnected anyway
-                return;
+        }
+        Self::prune(observers, dead);
+    }
+
+    fn consume_event(&self, rx: Receiver<E>, guard: ConsumerGuard) {
+        let weak_observers = Arc::downgrade(&self.observers);
+        thread::spawn(move || {
+            let _guard = guard;
+            loop {
+                let maybe_event = rx.recv();
+                if maybe_event.is_err() {
+                    // we have become disconnected
+                    return;
+                }
+                let event = maybe_event.unwrap();
+                let maybe_observers = weak_observers.upgrade();
+                if maybe_observers.is_none() {
+                    // doesn't really happen since we'd be disconnected anyway
+                    return;
+                }
+                let observers = maybe_observers.unwrap();
+
+                let dead: Vec<ObserverId> = Self::snapshot(&observers)
+                    .into_par_iter()
+                    .filter_map(|(id, registration)| {
+                        if registration.notify(&event) {
+                            None
+                        } else {
+                            Some(id)
+                        }
+                    })
+                    .collect();
+                Self::prune(&observers, dead);
             }
-            let observers = maybe_observers.unwrap();
-
-            observers
-                .read()
-                .unwrap()
-                .par_iter()
-                .for_each(|registration| {
-                    if let Some(observer) = registration.weak.upgrade() {
-                        observer.notify(&event);
-                    } else {
-                        // the observer has since freed
-                        // todo move into own function
-                        // todo will deadlock!!!
-                        observers.write().unwrap().remove(registration);
-                    }
-                });
         });
     }
 }
 
+/// Collects the changes of a layer while a batch is open, so they can be delivered as a single event
+/// once the outermost batch ends. Batches nest, a disabled batch never opens.
+#[derive(Default)]
+pub struct Batch<B> {
+    enabled: bool,
+    depth: usize,
+    pending: B,
+}
+
+impl<B: Default> Batch<B> {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.depth > 0
+    }
+
+    pub fn begin(&mut self) {
+        if self.enabled {
+            self.depth += 1;
+        }
+    }
+
+    /// true if this closed the outermost batch and the pending changes should be delivered
+    pub fn end(&mut self) -> bool {
+        if self.depth == 0 {
+            return false;
+        }
+        self.depth -= 1;
+        self.depth == 0
+    }
+
+    pub fn pending_mut(&mut self) -> &mut B {
+        &mut self.pending
+    }
+
+    pub fn take(&mut self) -> B {
+        std::mem::take(&mut self.pending)
+    }
+}
+
 pub trait Observable<E: 'static + Send + Sync> {
     fn observers(&self) -> &Observers<E>;
 
+    /// a failed delivery is logged, use `try_notify_all` to handle it
     fn notify_all(&self, event: E) {
         self.observers().queue_event(event);
     }
+
+    fn try_notify_all(&self, event: E) -> Result<(), NotifyError> {
+        self.observers().try_queue_event(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<(&'static str, usize)>>>,
+    }
+
+    impl Observer<usize> for Recorder {
+        fn notify(&self, event: &usize) {
+            self.log.lock().unwrap().push((self.name, *event));
+        }
+    }
+
+    struct Counter(Observers<usize>);
+
+    impl Observable<usize> for Counter {
+        fn observers(&self) -> &Observers<usize> {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn test_synchronous_dispatch() {
+        let counter = Counter(Observers::with_dispatch(Dispatch::Synchronous));
+        assert_eq!(counter.observers().dispatch(), Dispatch::Synchronous);
+        let log = Arc::new(Mutex::new(vec![]));
+        let first = Arc::new(Recorder {
+            name: "first",
+            log: log.clone(),
+        });
+        let second = Arc::new(Recorder {
+            name: "second",
+            log: log.clone(),
+        });
+        let _first_subscription = counter.observers().register(&first);
+        let _second_subscription = counter.observers().register(&second);
+        counter.notify_all(1);
+        drop(first);
+        counter.notify_all(2);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![("first", 1), ("second", 1), ("second", 2)]
+        );
+        assert_eq!(counter.observers().len(), 1);
+    }
+
+    #[test]
+    fn test_subscription_drop() {
+        let counter = Counter(Observers::synchronous());
+        let log = Arc::new(Mutex::new(vec![]));
+        let recorder = Subscribed::new(Recorder {
+            name: "recorder",
+            log: log.clone(),
+        })
+        .subscribe(counter.observers());
+        assert_eq!(counter.observers().len(), 1);
+        counter.notify_all(1);
+        drop(recorder);
+        assert!(counter.observers().is_empty());
+        counter.notify_all(2);
+        assert_eq!(*log.lock().unwrap(), vec![("recorder", 1)]);
+
+        let recorder = Arc::new(Recorder {
+            name: "recorder",
+            log,
+        });
+        let subscription = counter.observers().register(&recorder);
+        assert!(subscription.unsubscribe());
+        assert!(counter.observers().is_empty());
+    }
+
+    struct Gate {
+        entered: Mutex<Sender<()>>,
+        open: Mutex<()>,
+        log: Mutex<Vec<usize>>,
+    }
+
+    impl Observer<usize> for Gate {
+        fn notify(&self, event: &usize) {
+            self.entered.lock().unwrap().send(()).unwrap();
+            let _open = self.open.lock().unwrap();
+            self.log.lock().unwrap().push(*event);
+        }
+    }
+
+    /// blocks the consumer on the first event, then queues up four more into a queue of two
+    fn congest(backpressure: Backpressure) -> (Vec<usize>, usize) {
+        let counter = Counter(Observers::with_backpressure(2, backpressure));
+        let (entered_tx, entered_rx) = bounded(10);
+        let gate = Arc::new(Gate {
+            entered: Mutex::new(entered_tx),
+            open: Mutex::new(()),
+            log: Mutex::new(vec![]),
+        });
+        let _subscription = counter.observers().register(&gate);
+        {
+            let _closed = gate.open.lock().unwrap();
+            counter.try_notify_all(1).unwrap();
+            entered_rx.recv().unwrap();
+            for event in 2..=5 {
+                counter.try_notify_all(event).unwrap();
+            }
+        }
+        while gate.log.lock().unwrap().len() < 5 - counter.observers().dropped() {
+            thread::sleep(Duration::from_millis(1));
+        }
+        let log = gate.log.lock().unwrap().clone();
+        (log, counter.observers().dropped())
+    }
+
+    #[test]
+    fn test_backpressure() {
+        assert_eq!(congest(Backpressure::DropNewest), (vec![1, 2, 3], 2));
+        assert_eq!(congest(Backpressure::DropOldest), (vec![1, 4, 5], 2));
+        assert_eq!(congest(Backpressure::Coalesce), (vec![1, 5], 3));
+    }
 }
@@ -0,0 +1,228 @@
+use crate::coordinate::range::Range;
+use crate::map::buildings::{BuildingCreated, BuildingDestroyed, BuildingsChanged};
+use crate::map::fow::Uncover;
+use crate::map::territories::{TerritoriesChanged, TerritoryID, TerritoryJoined, TerritoryLeft};
+use crate::observable::{Observer, Observers, Subscription};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// The observers of one event type on the bus. They are notified inline by whoever routed the
+/// event, so they see the events in the order and on the thread of their source.
+pub struct Topic<E> {
+    observers: Observers<E>,
+}
+
+impl<E: 'static + Clone + Send + Sync> Observer<E> for Topic<E> {
+    fn notify(&self, event: &E) {
+        self.observers.queue_event(event.clone());
+    }
+}
+
+impl<E> Deref for Topic<E> {
+    type Target = Observers<E>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.observers
+    }
+}
+
+/// Single place to observe game events by type, regardless of which subsystem emits them.
+#[derive(Default)]
+pub struct EventBus {
+    topics: RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+    // keep the topics registered with the sources routed to them
+    routes: Mutex<Vec<Subscription>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// the observers of `E`, usable wherever an `Observers<E>` is expected
+    pub fn observers<E: 'static + Clone + Send + Sync>(&self) -> Arc<Topic<E>> {
+        let type_id = TypeId::of::<E>();
+        if let Some(topic) = self.topics.read().unwrap().get(&type_id) {
+            return Arc::clone(topic).downcast().unwrap();
+        }
+        let topic = Arc::clone(
+            self.topics
+                .write()
+                .unwrap()
+                .entry(type_id)
+                .or_insert_with(|| {
+                    Arc::new(Topic::<E> {
+                        observers: Observers::synchronous(),
+                    })
+                }),
+        );
+        topic.downcast().unwrap()
+    }
+
+    /// deliver every event of `source` to the observers on the bus as well
+    pub fn route<E: 'static + Clone + Send + Sync>(&self, source: &Observers<E>) {
+        let subscription = source.register(&self.observers::<E>());
+        self.routes.lock().unwrap().push(subscription);
+    }
+
+    /// for events that don't have an owner of their own
+    pub fn publish<E: 'static + Clone + Send + Sync>(&self, event: E) {
+        self.observers::<E>().queue_event(event);
+    }
+
+    pub fn subscribe<E, O>(&self, observer: &Arc<O>) -> Subscription
+    where
+        E: 'static + Clone + Send + Sync,
+        O: 'static + Observer<E>,
+    {
+        self.observers::<E>().register(observer)
+    }
+
+    pub fn subscribe_filtered<E, O>(
+        &self,
+        observer: &Arc<O>,
+        filter: impl Fn(&E) -> bool + Send + Sync + 'static,
+    ) -> Subscription
+    where
+        E: 'static + Clone + Send + Sync,
+        O: 'static + Observer<E>,
+    {
+        self.observers::<E>().register_filtered(observer, filter)
+    }
+}
+
+/// events that happen at one or more coordinates
+pub trait Located {
+    fn is_within(&self, range: &Range) -> bool;
+}
+
+/// events that concern one or more territories
+pub trait Territorial {
+    fn concerns(&self, territory_id: &TerritoryID) -> bool;
+}
+
+/// filter for events in `range`, a batch passes if any of its changes is in range
+pub fn within<E: Located>(range: Range) -> impl Fn(&E) -> bool + Send + Sync + 'static {
+    move |event: &E| event.is_within(&range)
+}
+
+/// filter for events of one territory, a batch passes if any of its changes concerns it
+pub fn in_territory<E: Territorial>(
+    territory_id: TerritoryID,
+) -> impl Fn(&E) -> bool + Send + Sync + 'static {
+    move |event: &E| event.concerns(&territory_id)
+}
+
+impl Located for Uncover {
+    fn is_within(&self, range: &Range) -> bool {
+        self.coordinates()
+            .iter()
+            .any(|coordinate| range.contains(coordinate))
+    }
+}
+
+impl Located for TerritoryJoined {
+    fn is_within(&self, range: &Range) -> bool {
+        range.contains(&self.coordinate)
+    }
+}
+
+impl Located for TerritoryLeft {
+    fn is_within(&self, range: &Range) -> bool {
+        range.contains(&self.coordinate)
+    }
+}
+
+impl Located for TerritoriesChanged {
+    fn is_within(&self, range: &Range) -> bool {
+        self.coordinates()
+            .iter()
+            .any(|coordinate| range.contains(coordinate))
+    }
+}
+
+impl Located for BuildingCreated {
+    fn is_within(&self, range: &Range) -> bool {
+        range.contains(&self.coordinate)
+    }
+}
+
+impl Located for BuildingDestroyed {
+    fn is_within(&self, range: &Range) -> bool {
+        range.contains(&self.coordinate)
+    }
+}
+
+impl Located for BuildingsChanged {
+    fn is_within(&self, range: &Range) -> bool {
+        self.coordinates()
+            .iter()
+            .any(|coordinate| range.contains(coordinate))
+    }
+}
+
+impl Territorial for TerritoryJoined {
+    fn concerns(&self, territory_id: &TerritoryID) -> bool {
+        &self.territory_id == territory_id
+    }
+}
+
+impl Territorial for TerritoryLeft {
+    fn concerns(&self, territory_id: &TerritoryID) -> bool {
+        &self.territory_id == territory_id
+    }
+}
+
+impl Territorial for TerritoriesChanged {
+    fn concerns(&self, territory_id: &TerritoryID) -> bool {
+        self.joined
+            .iter()
+            .any(|joined| joined.concerns(territory_id))
+            || self.left.iter().any(|left| left.concerns(territory_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinate::range::RangeFactory;
+    use crate::coordinate::Coordinate;
+    use crate::observable::Dispatch;
+
+    #[derive(Default)]
+    struct JoinRecorder(Mutex<Vec<Coordinate>>);
+
+    impl Observer<TerritoryJoined> for JoinRecorder {
+        fn notify(&self, event: &TerritoryJoined) {
+            self.0.lock().unwrap().push(event.coordinate);
+        }
+    }
+
+    #[test]
+    fn test_routed_and_filtered() {
+        let bus = EventBus::new();
+        let source = Observers::with_dispatch(Dispatch::Synchronous);
+        bus.route(&source);
+        let all = Arc::new(JoinRecorder::default());
+        let _all = bus.subscribe::<TerritoryJoined, _>(&all);
+        let nearby = Arc::new(JoinRecorder::default());
+        let _nearby = bus.subscribe_filtered(&nearby, within(Range::circle0(1)));
+        let mine = Arc::new(JoinRecorder::default());
+        let _mine = bus.subscribe_filtered(&mine, in_territory(TerritoryID::new(1)));
+
+        for (x, territory_id) in [(0, 1), (5, 1), (1, 2)].iter() {
+            source.queue_event(TerritoryJoined {
+                coordinate: Coordinate::new(*x, 0),
+                territory_id: TerritoryID::new(*territory_id),
+            });
+        }
+        let coordinates = |recorder: &JoinRecorder| -> Vec<i32> {
+            recorder.0.lock().unwrap().iter().map(|c| c.x()).collect()
+        };
+        assert_eq!(coordinates(&all), vec![0, 5, 1]);
+        assert_eq!(coordinates(&nearby), vec![0, 1]);
+        assert_eq!(coordinates(&mine), vec![0, 5]);
+    }
+}
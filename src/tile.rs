@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use strum_macros::{AsRefStr, EnumIter, EnumString, EnumVariantNames};
 
@@ -19,7 +20,19 @@ pub mod produces;
 pub mod state;
 mod warehouse;
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, EnumIter, AsRefStr, EnumString, EnumVariantNames)]
+#[derive(
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumIter,
+    AsRefStr,
+    EnumString,
+    EnumVariantNames,
+    Serialize,
+    Deserialize,
+)]
 pub enum TileName {
     Pioneer,
     Warehouse,